@@ -1,4 +1,9 @@
 pub mod prelude;
 
+pub mod membership;
 pub mod record;
+pub mod record_tag;
+pub mod record_usage;
+pub mod sea_orm_active_enums;
+pub mod tag;
 pub mod user;
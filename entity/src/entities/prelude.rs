@@ -1,7 +1,25 @@
+pub use super::membership::{
+    ActiveModel as MembershipActiveModel, Column as MembershipColumn, Entity as Membership,
+    Model as MembershipModel, PrimaryKey as MembershipPrimaryKey, Relation as MembershipRelation,
+};
 pub use super::record::{
     ActiveModel as RecordActiveModel, Column as RecordColumn, Entity as Record,
     Model as RecordModel, PrimaryKey as RecordPrimaryKey, Relation as RecordRelation,
 };
+pub use super::record_tag::{
+    ActiveModel as RecordTagActiveModel, Column as RecordTagColumn, Entity as RecordTag,
+    Model as RecordTagModel, PrimaryKey as RecordTagPrimaryKey, Relation as RecordTagRelation,
+};
+pub use super::record_usage::{
+    ActiveModel as RecordUsageActiveModel, Column as RecordUsageColumn, Entity as RecordUsage,
+    Model as RecordUsageModel, PrimaryKey as RecordUsagePrimaryKey,
+    Relation as RecordUsageRelation,
+};
+pub use super::sea_orm_active_enums::MediaKind;
+pub use super::tag::{
+    ActiveModel as TagActiveModel, Column as TagColumn, Entity as Tag, Model as TagModel,
+    PrimaryKey as TagPrimaryKey, Relation as TagRelation,
+};
 pub use super::user::{
     ActiveModel as UserActiveModel, Column as UserColumn, Entity as User, Model as UserModel,
     PrimaryKey as UserPrimaryKey, Relation as UserRelation,
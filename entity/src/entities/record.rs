@@ -1,5 +1,7 @@
 use sea_orm::entity::prelude::*;
 
+use super::sea_orm_active_enums::MediaKind;
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
 #[sea_orm(table_name = "records")]
 pub struct Model {
@@ -11,13 +13,67 @@ pub struct Model {
     #[sea_orm(indexed)]
     pub user_id: i64,
 
+    /// Telegram chat ID of the library, `None` for the global one
+    #[sea_orm(indexed, nullable)]
+    pub chat_id: Option<i64>,
+
     /// records
-    #[sea_orm(indexed, column_type = "Text", unique)]
+    #[sea_orm(indexed, column_type = "Text")]
     pub message: String,
 
+    /// media kind
+    #[sea_orm(default_value = "text")]
+    pub kind: MediaKind,
+
+    /// Telegram file ID, used to send the media back
+    #[sea_orm(nullable)]
+    pub file_id: Option<String>,
+
+    /// Telegram file unique ID, empty for text records
+    #[sea_orm(default_value = "")]
+    pub file_unique_id: String,
+
+    /// message entities serialized as JSON
+    #[sea_orm(column_type = "Text", nullable)]
+    pub entities: Option<String>,
+
     /// hot
     #[sea_orm(default_value = "0")]
     pub hot: i64,
+
+    /// approved by the quoted user, pending records are hidden from others
+    #[sea_orm(default_value = true)]
+    pub approved: bool,
+
+    /// unix time the record was moved to trash, `None` if it is not deleted
+    #[sea_orm(indexed, nullable)]
+    pub deleted_at: Option<i64>,
+
+    /// relation user id of who forwarded the record, `None` for old records
+    #[sea_orm(indexed, nullable)]
+    pub recorded_by: Option<i64>,
+
+    /// pinyin of the message for searching, `None` until computed
+    #[sea_orm(indexed, column_type = "Text", nullable)]
+    pub pinyin: Option<String>,
+
+    /// pinyin initials of the message for searching, `None` until computed
+    #[sea_orm(indexed, column_type = "Text", nullable)]
+    pub initials: Option<String>,
+
+    /// message folded to simplified script, half width and lowercase for
+    /// searching, `None` until computed
+    #[sea_orm(column_type = "Text", nullable)]
+    pub normalized: Option<String>,
+
+    /// unix time the record was saved, `None` for old records
+    #[sea_orm(indexed, nullable)]
+    pub created_at: Option<i64>,
+
+    /// uses decayed by age, as log2 of the sum of `2^(t / half life)` over
+    /// the time `t` of each use, so it orders records without updating them
+    #[sea_orm(indexed, default_value = "0")]
+    pub trend: f64,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -28,6 +84,19 @@ pub enum Relation {
         to = "super::user::Column::Id"
     )]
     User,
+
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::RecordedBy",
+        to = "super::user::Column::Id"
+    )]
+    RecordedBy,
+
+    #[sea_orm(has_many = "super::record_tag::Entity")]
+    RecordTag,
+
+    #[sea_orm(has_many = "super::record_usage::Entity")]
+    RecordUsage,
 }
 
 impl Related<super::user::Entity> for Entity {
@@ -36,4 +105,26 @@ impl Related<super::user::Entity> for Entity {
     }
 }
 
+impl Related<super::record_tag::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RecordTag.def()
+    }
+}
+
+impl Related<super::record_usage::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RecordUsage.def()
+    }
+}
+
+impl Related<super::tag::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::record_tag::Relation::Tag.def()
+    }
+
+    fn via() -> Option<RelationDef> {
+        Some(super::record_tag::Relation::Record.def().rev())
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
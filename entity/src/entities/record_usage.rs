@@ -0,0 +1,38 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "record_usages")]
+pub struct Model {
+    /// Telegram user ID of who sent the record through inline query
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub tg_uid: i64,
+
+    /// relation record id
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub record_id: i64,
+
+    /// times the user sent the record
+    #[sea_orm(default_value = "0")]
+    pub count: i64,
+
+    /// unix time the user last sent the record
+    pub used_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::record::Entity",
+        from = "Column::RecordId",
+        to = "super::record::Column::Id"
+    )]
+    Record,
+}
+
+impl Related<super::record::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Record.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,35 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+pub enum MediaKind {
+    /// plain text message
+    #[sea_orm(string_value = "text")]
+    Text,
+
+    /// sticker
+    #[sea_orm(string_value = "sticker")]
+    Sticker,
+
+    /// photo
+    #[sea_orm(string_value = "photo")]
+    Photo,
+
+    /// GIF animation
+    #[sea_orm(string_value = "animation")]
+    Animation,
+
+    /// voice note
+    #[sea_orm(string_value = "voice")]
+    Voice,
+
+    /// video
+    #[sea_orm(string_value = "video")]
+    Video,
+}
+
+impl Default for MediaKind {
+    fn default() -> Self {
+        MediaKind::Text
+    }
+}
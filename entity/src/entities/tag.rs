@@ -0,0 +1,37 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "tags")]
+pub struct Model {
+    /// internal ID
+    #[sea_orm(primary_key)]
+    pub id: i64,
+
+    /// tag name, lowercase without the leading `#`
+    #[sea_orm(unique)]
+    pub name: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::record_tag::Entity")]
+    RecordTag,
+}
+
+impl Related<super::record_tag::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RecordTag.def()
+    }
+}
+
+impl Related<super::record::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::record_tag::Relation::Record.def()
+    }
+
+    fn via() -> Option<RelationDef> {
+        Some(super::record_tag::Relation::Tag.def().rev())
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -18,12 +18,27 @@ pub struct Model {
     /// use notify
     #[sea_orm(default_value = true)]
     pub notify: bool,
+
+    /// search every library of the chats the user belongs to
+    #[sea_orm(default_value = false)]
+    pub search_all_chats: bool,
+
+    /// refuse to be recorded
+    #[sea_orm(default_value = false)]
+    pub opt_out: bool,
+
+    /// new records about the user wait for approval
+    #[sea_orm(default_value = false)]
+    pub consent: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(has_many = "super::record::Entity")]
     Record,
+
+    #[sea_orm(has_many = "super::membership::Entity")]
+    Membership,
 }
 
 impl Related<super::record::Entity> for Entity {
@@ -32,4 +47,10 @@ impl Related<super::record::Entity> for Entity {
     }
 }
 
+impl Related<super::membership::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Membership.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
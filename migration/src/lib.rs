@@ -3,6 +3,23 @@ pub use sea_orm_migration::prelude::*;
 mod m20220101_000001_create_table;
 mod m20220625_222908_message_unique;
 mod m20220630_195724_for_hot;
+mod m20261018_000001_record_media;
+mod m20261018_000002_record_entities;
+mod m20261018_000003_chat_scope;
+mod m20261018_000004_tags;
+mod m20261018_000005_user_opt_out;
+mod m20261018_000006_consent;
+mod m20261018_000007_record_author_unique;
+mod m20261018_000008_record_trash;
+mod m20261018_000009_record_recorded_by;
+mod m20261018_000010_record_fulltext;
+mod m20261018_000011_record_pinyin;
+mod m20261018_000012_record_normalized;
+mod m20261018_000013_record_created_at;
+mod m20261018_000014_record_trigram;
+mod m20261018_000015_record_trend;
+mod m20261018_000016_record_usages;
+mod m20261018_000017_record_chat_unique;
 
 pub struct Migrator;
 
@@ -13,6 +30,23 @@ impl MigratorTrait for Migrator {
             Box::new(m20220101_000001_create_table::Migration),
             Box::new(m20220625_222908_message_unique::Migration),
             Box::new(m20220630_195724_for_hot::Migration),
+            Box::new(m20261018_000001_record_media::Migration),
+            Box::new(m20261018_000002_record_entities::Migration),
+            Box::new(m20261018_000003_chat_scope::Migration),
+            Box::new(m20261018_000004_tags::Migration),
+            Box::new(m20261018_000005_user_opt_out::Migration),
+            Box::new(m20261018_000006_consent::Migration),
+            Box::new(m20261018_000007_record_author_unique::Migration),
+            Box::new(m20261018_000008_record_trash::Migration),
+            Box::new(m20261018_000009_record_recorded_by::Migration),
+            Box::new(m20261018_000010_record_fulltext::Migration),
+            Box::new(m20261018_000011_record_pinyin::Migration),
+            Box::new(m20261018_000012_record_normalized::Migration),
+            Box::new(m20261018_000013_record_created_at::Migration),
+            Box::new(m20261018_000014_record_trigram::Migration),
+            Box::new(m20261018_000015_record_trend::Migration),
+            Box::new(m20261018_000016_record_usages::Migration),
+            Box::new(m20261018_000017_record_chat_unique::Migration),
         ]
     }
 }
@@ -0,0 +1,113 @@
+use models::prelude::{Record, RecordColumn};
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+const RECORD_MESSAGE_UNIQUE: &str = "record_message_unique";
+const RECORD_MESSAGE_MEDIA_UNIQUE: &str = "record_message_media_unique";
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20261018_000001_record_media"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Record)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Alias::new("kind"))
+                            .string_len(16)
+                            .not_null()
+                            .default("text"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Record)
+                    .add_column_if_not_exists(ColumnDef::new(Alias::new("file_id")).text())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Record)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Alias::new("file_unique_id"))
+                            .string()
+                            .not_null()
+                            .default(""),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // media records may share the same (empty) text, so the message alone
+        // is no longer unique.
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(Record)
+                    .name(RECORD_MESSAGE_UNIQUE)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(Record)
+                    .col(RecordColumn::Message)
+                    .col(RecordColumn::FileUniqueId)
+                    .name(RECORD_MESSAGE_MEDIA_UNIQUE)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(Record)
+                    .name(RECORD_MESSAGE_MEDIA_UNIQUE)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(Record)
+                    .col(RecordColumn::Message)
+                    .name(RECORD_MESSAGE_UNIQUE)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        for column in ["file_unique_id", "file_id", "kind"] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Record)
+                        .drop_column(Alias::new(column))
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
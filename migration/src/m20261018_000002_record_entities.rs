@@ -0,0 +1,35 @@
+use models::prelude::Record;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20261018_000002_record_entities"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Record)
+                    .add_column_if_not_exists(ColumnDef::new(Alias::new("entities")).text())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Record)
+                    .drop_column(Alias::new("entities"))
+                    .to_owned(),
+            )
+            .await
+    }
+}
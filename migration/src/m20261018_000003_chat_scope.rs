@@ -0,0 +1,115 @@
+use models::{
+    membership,
+    prelude::{Membership, MembershipColumn, Record, RecordColumn, User},
+};
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{ConnectionTrait, Schema},
+};
+
+pub struct Migration;
+
+const RECORD_CHAT_INDEX: &str = "record_chat_index";
+const MEMBERSHIP_UNIQUE: &str = "membership_unique";
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20261018_000003_chat_scope"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let builder = db.get_database_backend();
+        let schema = Schema::new(builder);
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Record)
+                    .add_column_if_not_exists(ColumnDef::new(Alias::new("chat_id")).big_integer())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(Record)
+                    .col(RecordColumn::ChatId)
+                    .name(RECORD_CHAT_INDEX)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Alias::new("search_all_chats"))
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        db.execute(
+            builder.build(
+                schema
+                    .create_table_from_entity(membership::Entity)
+                    .if_not_exists(),
+            ),
+        )
+        .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(Membership)
+                    .col(MembershipColumn::UserId)
+                    .col(MembershipColumn::ChatId)
+                    .name(MEMBERSHIP_UNIQUE)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Membership).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User)
+                    .drop_column(Alias::new("search_all_chats"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(Record)
+                    .name(RECORD_CHAT_INDEX)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Record)
+                    .drop_column(Alias::new("chat_id"))
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,59 @@
+use models::{
+    prelude::{RecordTag, RecordTagColumn, Tag},
+    record_tag, tag,
+};
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{ConnectionTrait, Schema},
+};
+
+pub struct Migration;
+
+const RECORD_TAG_TAG_INDEX: &str = "record_tag_tag_index";
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20261018_000004_tags"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let builder = db.get_database_backend();
+        let schema = Schema::new(builder);
+
+        db.execute(builder.build(schema.create_table_from_entity(tag::Entity).if_not_exists()))
+            .await?;
+
+        db.execute(
+            builder.build(
+                schema
+                    .create_table_from_entity(record_tag::Entity)
+                    .if_not_exists(),
+            ),
+        )
+        .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(RecordTag)
+                    .col(RecordTagColumn::TagId)
+                    .name(RECORD_TAG_TAG_INDEX)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RecordTag).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Tag).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,65 @@
+use models::prelude::{Record, RecordColumn};
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+const RECORD_MESSAGE_MEDIA_UNIQUE: &str = "record_message_media_unique";
+const RECORD_AUTHOR_UNIQUE: &str = "record_author_unique";
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20261018_000007_record_author_unique"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // different people may say the same thing, only one author's records
+        // have to be unique.
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(Record)
+                    .name(RECORD_MESSAGE_MEDIA_UNIQUE)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(Record)
+                    .col(RecordColumn::UserId)
+                    .col(RecordColumn::Message)
+                    .col(RecordColumn::FileUniqueId)
+                    .name(RECORD_AUTHOR_UNIQUE)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(Record)
+                    .name(RECORD_AUTHOR_UNIQUE)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(Record)
+                    .col(RecordColumn::Message)
+                    .col(RecordColumn::FileUniqueId)
+                    .name(RECORD_MESSAGE_MEDIA_UNIQUE)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+}
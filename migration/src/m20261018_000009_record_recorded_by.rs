@@ -0,0 +1,58 @@
+use models::prelude::{Record, RecordColumn};
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+const RECORD_RECORDED_BY_INDEX: &str = "record_recorded_by_index";
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20261018_000009_record_recorded_by"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Record)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Alias::new("recorded_by")).big_integer(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(Record)
+                    .col(RecordColumn::RecordedBy)
+                    .name(RECORD_RECORDED_BY_INDEX)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(Record)
+                    .name(RECORD_RECORDED_BY_INDEX)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Record)
+                    .drop_column(Alias::new("recorded_by"))
+                    .to_owned(),
+            )
+            .await
+    }
+}
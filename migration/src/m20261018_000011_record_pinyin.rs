@@ -0,0 +1,71 @@
+use models::prelude::{Record, RecordColumn};
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+const RECORD_PINYIN_INDEX: &str = "record_pinyin_index";
+const RECORD_INITIALS_INDEX: &str = "record_initials_index";
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20261018_000011_record_pinyin"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // existing records are left `NULL` and backfilled on startup
+        for column in ["pinyin", "initials"] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Record)
+                        .add_column_if_not_exists(ColumnDef::new(Alias::new(column)).text())
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(Record)
+                    .col(RecordColumn::Pinyin)
+                    .name(RECORD_PINYIN_INDEX)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(Record)
+                    .col(RecordColumn::Initials)
+                    .name(RECORD_INITIALS_INDEX)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for index in [RECORD_INITIALS_INDEX, RECORD_PINYIN_INDEX] {
+            manager
+                .drop_index(Index::drop().table(Record).name(index).to_owned())
+                .await?;
+        }
+
+        for column in ["initials", "pinyin"] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Record)
+                        .drop_column(Alias::new(column))
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
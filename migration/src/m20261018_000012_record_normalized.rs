@@ -0,0 +1,130 @@
+use models::prelude::Record;
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{ConnectionTrait, DbBackend, Statement},
+};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20261018_000012_record_normalized"
+    }
+}
+
+/// The full-text index moves from `message` to `normalized`, which is
+/// backfilled on startup and kept in sync by the same triggers.
+const SQLITE_UP: &[&str] = &[
+    "DROP TRIGGER IF EXISTS records_fts_update",
+    "DROP TRIGGER IF EXISTS records_fts_delete",
+    "DROP TRIGGER IF EXISTS records_fts_insert",
+    "DROP TABLE IF EXISTS records_fts",
+    "CREATE VIRTUAL TABLE IF NOT EXISTS records_fts USING fts5(normalized, content = 'records', content_rowid = 'id', tokenize = 'trigram')",
+    "CREATE TRIGGER IF NOT EXISTS records_fts_insert AFTER INSERT ON records BEGIN
+        INSERT INTO records_fts (rowid, normalized) VALUES (new.id, new.normalized);
+    END",
+    "CREATE TRIGGER IF NOT EXISTS records_fts_delete AFTER DELETE ON records BEGIN
+        INSERT INTO records_fts (records_fts, rowid, normalized) VALUES ('delete', old.id, old.normalized);
+    END",
+    "CREATE TRIGGER IF NOT EXISTS records_fts_update AFTER UPDATE OF normalized ON records BEGIN
+        INSERT INTO records_fts (records_fts, rowid, normalized) VALUES ('delete', old.id, old.normalized);
+        INSERT INTO records_fts (rowid, normalized) VALUES (new.id, new.normalized);
+    END",
+    "INSERT INTO records_fts (records_fts) VALUES ('rebuild')",
+];
+
+const SQLITE_DOWN: &[&str] = &[
+    "DROP TRIGGER IF EXISTS records_fts_update",
+    "DROP TRIGGER IF EXISTS records_fts_delete",
+    "DROP TRIGGER IF EXISTS records_fts_insert",
+    "DROP TABLE IF EXISTS records_fts",
+    "CREATE VIRTUAL TABLE IF NOT EXISTS records_fts USING fts5(message, content = 'records', content_rowid = 'id', tokenize = 'trigram')",
+    "CREATE TRIGGER IF NOT EXISTS records_fts_insert AFTER INSERT ON records BEGIN
+        INSERT INTO records_fts (rowid, message) VALUES (new.id, new.message);
+    END",
+    "CREATE TRIGGER IF NOT EXISTS records_fts_delete AFTER DELETE ON records BEGIN
+        INSERT INTO records_fts (records_fts, rowid, message) VALUES ('delete', old.id, old.message);
+    END",
+    "CREATE TRIGGER IF NOT EXISTS records_fts_update AFTER UPDATE OF message ON records BEGIN
+        INSERT INTO records_fts (records_fts, rowid, message) VALUES ('delete', old.id, old.message);
+        INSERT INTO records_fts (rowid, message) VALUES (new.id, new.message);
+    END",
+    "INSERT INTO records_fts (records_fts) VALUES ('rebuild')",
+];
+
+const POSTGRES_UP: &[&str] = &[
+    "DROP INDEX IF EXISTS record_message_tsv_index",
+    "ALTER TABLE records DROP COLUMN IF EXISTS message_tsv",
+    "ALTER TABLE records ADD COLUMN IF NOT EXISTS normalized_tsv tsvector GENERATED ALWAYS AS (to_tsvector('simple', regexp_replace(coalesce(normalized, ''), '([\\u3040-\\u30ff\\u3400-\\u9fff\\uac00-\\ud7af])', ' \\1 ', 'g'))) STORED",
+    "CREATE INDEX IF NOT EXISTS record_normalized_tsv_index ON records USING GIN (normalized_tsv)",
+];
+
+const POSTGRES_DOWN: &[&str] = &[
+    "DROP INDEX IF EXISTS record_normalized_tsv_index",
+    "ALTER TABLE records DROP COLUMN IF EXISTS normalized_tsv",
+    "ALTER TABLE records ADD COLUMN IF NOT EXISTS message_tsv tsvector GENERATED ALWAYS AS (to_tsvector('simple', regexp_replace(message, '([\\u3040-\\u30ff\\u3400-\\u9fff\\uac00-\\ud7af])', ' \\1 ', 'g'))) STORED",
+    "CREATE INDEX IF NOT EXISTS record_message_tsv_index ON records USING GIN (message_tsv)",
+];
+
+const MYSQL_UP: &[&str] = &[
+    "ALTER TABLE records DROP INDEX record_message_fulltext",
+    "ALTER TABLE records ADD FULLTEXT INDEX record_normalized_fulltext (normalized) WITH PARSER ngram",
+];
+
+const MYSQL_DOWN: &[&str] = &[
+    "ALTER TABLE records DROP INDEX record_normalized_fulltext",
+    "ALTER TABLE records ADD FULLTEXT INDEX record_message_fulltext (message) WITH PARSER ngram",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // existing records are left `NULL` and backfilled on startup
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Record)
+                    .add_column_if_not_exists(ColumnDef::new(Alias::new("normalized")).text())
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        let builder = db.get_database_backend();
+        let statements = match builder {
+            DbBackend::Sqlite => SQLITE_UP,
+            DbBackend::Postgres => POSTGRES_UP,
+            DbBackend::MySql => MYSQL_UP,
+        };
+
+        for statement in statements {
+            db.execute(Statement::from_string(builder, statement.to_string()))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let builder = db.get_database_backend();
+        let statements = match builder {
+            DbBackend::Sqlite => SQLITE_DOWN,
+            DbBackend::Postgres => POSTGRES_DOWN,
+            DbBackend::MySql => MYSQL_DOWN,
+        };
+
+        for statement in statements {
+            db.execute(Statement::from_string(builder, statement.to_string()))
+                .await?;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Record)
+                    .drop_column(Alias::new("normalized"))
+                    .to_owned(),
+            )
+            .await
+    }
+}
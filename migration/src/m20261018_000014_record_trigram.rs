@@ -0,0 +1,52 @@
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{ConnectionTrait, DbBackend, Statement},
+};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20261018_000014_record_trigram"
+    }
+}
+
+/// Postgres ranks fuzzy matches with `pg_trgm`, other backends compute the
+/// similarity in the bot and need nothing here.
+const POSTGRES_UP: &[&str] = &[
+    "CREATE EXTENSION IF NOT EXISTS pg_trgm",
+    "CREATE INDEX IF NOT EXISTS record_normalized_trgm_index ON records USING GIN (normalized gin_trgm_ops)",
+];
+
+const POSTGRES_DOWN: &[&str] = &["DROP INDEX IF EXISTS record_normalized_trgm_index"];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let builder = db.get_database_backend();
+        if builder != DbBackend::Postgres {
+            return Ok(());
+        }
+
+        for statement in POSTGRES_UP {
+            db.execute(Statement::from_string(builder, statement.to_string()))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let builder = db.get_database_backend();
+        if builder != DbBackend::Postgres {
+            return Ok(());
+        }
+
+        for statement in POSTGRES_DOWN {
+            db.execute(Statement::from_string(builder, statement.to_string()))
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+use models::{
+    prelude::{RecordUsage, RecordUsageColumn},
+    record_usage,
+};
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{ConnectionTrait, Schema},
+};
+
+pub struct Migration;
+
+const RECORD_USAGE_RECORD_INDEX: &str = "record_usage_record_index";
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20261018_000016_record_usages"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let builder = db.get_database_backend();
+        let schema = Schema::new(builder);
+
+        db.execute(
+            builder.build(
+                schema
+                    .create_table_from_entity(record_usage::Entity)
+                    .if_not_exists(),
+            ),
+        )
+        .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(RecordUsage)
+                    .col(RecordUsageColumn::RecordId)
+                    .name(RECORD_USAGE_RECORD_INDEX)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RecordUsage).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,74 @@
+use models::prelude::{Record, RecordColumn};
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{ConnectionTrait, DbBackend, Statement},
+};
+
+pub struct Migration;
+
+const RECORD_AUTHOR_UNIQUE: &str = "record_author_unique";
+const RECORD_AUTHOR_CHAT_UNIQUE: &str = "record_author_chat_unique";
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20261018_000017_record_chat_unique"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // every library has its own copy of a quote. NULLs are distinct in a
+        // unique index, so the global library (no chat) counts as chat 0,
+        // which no chat has.
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(Record)
+                    .name(RECORD_AUTHOR_UNIQUE)
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        let builder = db.get_database_backend();
+        let chat = match builder {
+            // functional key parts need their own parentheses
+            DbBackend::MySql => "(COALESCE(chat_id, 0))",
+            _ => "COALESCE(chat_id, 0)",
+        };
+        db.execute(Statement::from_string(
+            builder,
+            format!(
+                "CREATE UNIQUE INDEX {} ON records (user_id, message, file_unique_id, {})",
+                RECORD_AUTHOR_CHAT_UNIQUE, chat
+            ),
+        ))
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(Record)
+                    .name(RECORD_AUTHOR_CHAT_UNIQUE)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(Record)
+                    .col(RecordColumn::UserId)
+                    .col(RecordColumn::Message)
+                    .col(RecordColumn::FileUniqueId)
+                    .name(RECORD_AUTHOR_UNIQUE)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -1,15 +1,43 @@
-use teloxide::utils::command::BotCommands;
+use teloxide::utils::command::{BotCommands, ParseError};
 
 #[derive(PartialEq, Debug, BotCommands)]
 #[command(rename = "lowercase", prefix = "!")]
 pub enum CallbackCommands {
-    #[command(description = "internal command page", parse_with = "split")]
+    #[command(
+        description = "internal command page",
+        parse_with = "page_command_parser"
+    )]
     Page {
         msg_id: i32,
         username: String,
         page: usize,
+        filtered: bool,
     },
 
+    #[command(description = "internal command recorded page", parse_with = "split")]
+    Recorded { recorder: i64, page: usize },
+
+    #[command(description = "internal command approve")]
+    Approve { id: i64 },
+
+    #[command(description = "internal command reject")]
+    Reject { id: i64 },
+
+    #[command(description = "internal command undo")]
+    Undo { id: i64 },
+
+    #[command(description = "internal command import")]
+    Import { confirm: bool },
+
+    #[command(description = "internal command chat import")]
+    ChatImport { author: i64 },
+
+    #[command(description = "internal command delete all")]
+    DeleteAll,
+
+    #[command(description = "internal command cancel delete all")]
+    CancelDeleteAll,
+
     #[command(description = "default dummy command")]
     Default,
 }
@@ -19,3 +47,35 @@ impl Default for CallbackCommands {
         CallbackCommands::Default
     }
 }
+
+fn page_command_parser(input: String) -> Result<(i32, String, usize, bool), ParseError> {
+    let mut args = input.splitn(4, ' ');
+    let msg_id = args
+        .next()
+        .unwrap_or_default()
+        .parse::<i32>()
+        .map_err(|error| ParseError::IncorrectFormat(error.into()))?;
+    let username = match args.next() {
+        Some(username) => username.to_string(),
+        None => return Err(ParseError::IncorrectFormat("missing username".into())),
+    };
+    let page = args
+        .next()
+        .unwrap_or_default()
+        .parse::<usize>()
+        .map_err(|error| ParseError::IncorrectFormat(error.into()))?;
+    let filtered = args.next() == Some("f");
+
+    Ok((msg_id, username, page, filtered))
+}
+
+/// callback data of a `/list` page.
+///
+/// The filter doesn't fit in the 64 bytes Telegram allows, it is read again
+/// from the `/list` message, `filtered` tells there is one to find.
+pub fn page_callback_data(msg_id: i32, username: &str, page: usize, filtered: bool) -> String {
+    match filtered {
+        true => format!("!page {} {} {} f", msg_id, username, page),
+        false => format!("!page {} {} {}", msg_id, username, page),
+    }
+}
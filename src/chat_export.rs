@@ -0,0 +1,432 @@
+use std::{collections::HashMap, path::Path};
+
+use crate::db_controller::{AddRecordResult, Controller, RecordContent};
+use crate::formatting::{entities_to_json, utf16_len};
+use chrono::NaiveDateTime;
+use migration::DbErr;
+use models::prelude::{MediaKind, RecordModel};
+use serde_json::Value;
+use teloxide::types::{MessageEntity, MessageEntityKind};
+use wd_log::{log_info_ln, log_panic};
+
+/// messages imported between progress reports
+pub const IMPORT_BATCH: usize = 100;
+
+/// keys of messages with media, which can't be sent back without a file ID
+const MEDIA_KEYS: &[&str] = &[
+    "photo",
+    "file",
+    "media_type",
+    "sticker_emoji",
+    "poll",
+    "location_information",
+    "contact_information",
+];
+
+/// a text message of a Telegram Desktop chat export.
+pub struct ChatMessage {
+    /// Telegram user ID of the author
+    pub author_id: i64,
+    /// display name of the author in the export
+    pub author: String,
+    pub text: String,
+    /// message entities serialized as JSON
+    pub entities: Option<String>,
+    /// unix time the message was sent
+    pub date: i64,
+}
+
+/// messages of the `result.json` written by "Export chat history" of Telegram
+/// Desktop.
+///
+/// Only text messages written by users are kept, media and forwarded
+/// messages are skipped.
+pub struct ChatExport {
+    pub messages: Vec<ChatMessage>,
+}
+
+/// what importing the messages of a chat export did.
+#[derive(Debug, Default)]
+pub struct ChatImportReport {
+    /// messages to import
+    pub total: usize,
+    pub added: usize,
+    /// already recorded
+    pub duplicate: usize,
+    /// the author refused to be recorded
+    pub opted_out: usize,
+    /// added but waiting for the consent of the author
+    pub pending: usize,
+}
+
+impl ChatImportReport {
+    pub fn done(&self) -> usize {
+        self.added + self.duplicate + self.opted_out
+    }
+}
+
+impl ChatExport {
+    /// read a single chat export, or every chat of a full account export.
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        let root: Value = serde_json::from_slice(data).map_err(|error| error.to_string())?;
+
+        let chats: Vec<&Value> = match root.get("chats").and_then(|chats| chats.get("list")) {
+            Some(Value::Array(list)) => list.iter().collect(),
+            _ if root.get("messages").is_some() => vec![&root],
+            _ => return Err("not a Telegram Desktop chat export".to_string()),
+        };
+
+        Ok(Self {
+            messages: chats
+                .into_iter()
+                .filter_map(|chat| chat.get("messages")?.as_array())
+                .flatten()
+                .filter_map(chat_message)
+                .collect(),
+        })
+    }
+
+    /// Telegram user ID, name and message count of each author, most
+    /// messages first.
+    pub fn authors(&self) -> Vec<(i64, String, usize)> {
+        let mut authors: HashMap<i64, (String, usize)> = HashMap::new();
+        for message in self.messages.iter() {
+            authors
+                .entry(message.author_id)
+                .or_insert_with(|| (message.author.to_owned(), 0))
+                .1 += 1;
+        }
+
+        let mut authors: Vec<(i64, String, usize)> = authors
+            .into_iter()
+            .map(|(id, (name, count))| (id, name, count))
+            .collect();
+        authors.sort_by(|(a_id, _, a), (b_id, _, b)| b.cmp(a).then(a_id.cmp(b_id)));
+        authors
+    }
+
+    /// messages written by any of `authors`, oldest first.
+    pub fn messages_of(&self, authors: &[i64]) -> Vec<&ChatMessage> {
+        let mut messages: Vec<&ChatMessage> = self
+            .messages
+            .iter()
+            .filter(|message| authors.contains(&message.author_id))
+            .collect();
+        messages.sort_by_key(|message| message.date);
+        messages
+    }
+}
+
+/// save `messages` as records of the global library recorded by
+/// `recorder_id`, with the rules of forwarded messages.
+///
+/// Big exports are imported in batches, so callers can report progress
+/// between them.
+///
+/// Names in the export may be forged, so authors get no username from it,
+/// unknown ones have none until they use the bot.
+///
+/// Returns the added records with the Telegram user ID of their author, who
+/// should be told about them.
+pub async fn import_messages(
+    controller: &Controller,
+    messages: &[&ChatMessage],
+    recorder_id: i64,
+    recorder_name: Option<&String>,
+    report: &mut ChatImportReport,
+) -> Result<Vec<(i64, RecordModel)>, DbErr> {
+    let mut added = vec![];
+
+    for message in messages {
+        let content = RecordContent {
+            kind: MediaKind::Text,
+            text: message.text.to_owned(),
+            entities: message.entities.to_owned(),
+            file_id: None,
+            file_unique_id: String::new(),
+            created_at: Some(message.date),
+        };
+        match controller
+            .add_record(
+                message.author_id,
+                None,
+                recorder_id,
+                recorder_name,
+                None,
+                content,
+            )
+            .await?
+        {
+            AddRecordResult::Added(record) => {
+                report.added += 1;
+                if !record.approved {
+                    report.pending += 1;
+                }
+                added.push((message.author_id, record));
+            }
+            AddRecordResult::Duplicate(_) => report.duplicate += 1,
+            AddRecordResult::OptedOut => report.opted_out += 1,
+        }
+    }
+
+    Ok(added)
+}
+
+/// import messages of `authors` from the chat export at `path`, or list its
+/// authors if none is given.
+pub async fn import_chat_file(
+    controller: &Controller,
+    path: &Path,
+    authors: &[i64],
+    recorder_id: i64,
+) {
+    let chat = match std::fs::read(path).map_err(|error| error.to_string()) {
+        Ok(data) => match ChatExport::parse(&data) {
+            Ok(chat) => chat,
+            Err(error) => log_panic!("cannot parse {}: {}", path.display(), error),
+        },
+        Err(error) => log_panic!("cannot read {}: {}", path.display(), error),
+    };
+
+    if authors.is_empty() {
+        for (id, name, count) in chat.authors() {
+            log_info_ln!("author id={}, messages={}, name=\"{}\"", id, count, name);
+        }
+        return;
+    }
+
+    let messages = chat.messages_of(authors);
+    let mut report = ChatImportReport {
+        total: messages.len(),
+        ..Default::default()
+    };
+    for batch in messages.chunks(IMPORT_BATCH) {
+        // authors can't be told from here, pending records are logged below
+        if let Err(error) = import_messages(controller, batch, recorder_id, None, &mut report).await
+        {
+            controller.err_handler(error);
+            return;
+        }
+        log_info_ln!("imported {}/{} messages", report.done(), report.total);
+    }
+
+    log_info_ln!(
+        "import finished: added={}, pending={}, duplicate={}, opted_out={}",
+        report.added,
+        report.pending,
+        report.duplicate,
+        report.opted_out
+    );
+}
+
+fn chat_message(message: &Value) -> Option<ChatMessage> {
+    if message.get("type")?.as_str()? != "message"
+        || message.get("forwarded_from").is_some()
+        || message.get("via_bot").is_some()
+        || MEDIA_KEYS.iter().any(|key| message.get(key).is_some())
+    {
+        return None;
+    }
+
+    let author_id = message
+        .get("from_id")?
+        .as_str()?
+        .strip_prefix("user")?
+        .parse::<i64>()
+        .ok()?;
+    let (text, entities) = message_text(message);
+    if text.trim().is_empty() {
+        return None;
+    }
+
+    Some(ChatMessage {
+        author_id,
+        author: message
+            .get("from")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        text,
+        entities: entities_to_json(Some(entities.as_slice())),
+        date: message_date(message)?,
+    })
+}
+
+/// `date_unixtime` of newer exports, or the local `date` of older ones.
+fn message_date(message: &Value) -> Option<i64> {
+    if let Some(date) = message.get("date_unixtime").and_then(Value::as_str) {
+        return date.parse::<i64>().ok();
+    }
+
+    NaiveDateTime::parse_from_str(message.get("date")?.as_str()?, "%Y-%m-%dT%H:%M:%S")
+        .ok()
+        .map(|date| date.and_utc().timestamp())
+}
+
+/// text and formatting of a message, from the parts of its `text`.
+fn message_text(message: &Value) -> (String, Vec<MessageEntity>) {
+    let parts = match message.get("text") {
+        Some(Value::String(text)) => return (text.to_owned(), vec![]),
+        Some(Value::Array(parts)) => parts,
+        _ => return (String::new(), vec![]),
+    };
+
+    let mut text = String::new();
+    let mut entities = vec![];
+    for part in parts {
+        let (part_text, kind) = match part {
+            Value::String(part_text) => (part_text.as_str(), None),
+            part => (
+                part.get("text").and_then(Value::as_str).unwrap_or_default(),
+                entity_kind(part),
+            ),
+        };
+
+        if let Some(kind) = kind {
+            entities.push(MessageEntity {
+                kind,
+                offset: utf16_len(&text),
+                length: utf16_len(part_text),
+            });
+        }
+        text.push_str(part_text);
+    }
+
+    (text, entities)
+}
+
+/// formatting of a text part, links and mentions are found by clients anyway.
+fn entity_kind(part: &Value) -> Option<MessageEntityKind> {
+    match part.get("type")?.as_str()? {
+        "bold" => Some(MessageEntityKind::Bold),
+        "italic" => Some(MessageEntityKind::Italic),
+        "underline" => Some(MessageEntityKind::Underline),
+        "strikethrough" => Some(MessageEntityKind::Strikethrough),
+        "spoiler" => Some(MessageEntityKind::Spoiler),
+        "code" => Some(MessageEntityKind::Code),
+        "pre" => Some(MessageEntityKind::Pre {
+            language: part
+                .get("language")
+                .and_then(Value::as_str)
+                .filter(|language| !language.is_empty())
+                .map(str::to_string),
+        }),
+        "text_link" => Some(MessageEntityKind::TextLink {
+            url: reqwest::Url::parse(part.get("href")?.as_str()?).ok()?,
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a chat export with a formatted message, a media message, a forwarded
+    /// message and a service message.
+    const FIXTURE: &str = r#"{
+        "name": "Test",
+        "type": "private_group",
+        "id": 1,
+        "messages": [
+            {
+                "id": 1,
+                "type": "message",
+                "date": "2024-01-01T08:00:00",
+                "date_unixtime": "1704096000",
+                "from": "Alice",
+                "from_id": "user123",
+                "text": [
+                    "hi 😀 ",
+                    {"type": "bold", "text": "粗体"},
+                    " and ",
+                    {"type": "text_link", "text": "link", "href": "https://example.com/"},
+                    {"type": "link", "text": "https://t.me"}
+                ]
+            },
+            {
+                "id": 2,
+                "type": "message",
+                "date": "2024-01-01T08:01:00",
+                "from": "Alice",
+                "from_id": "user123",
+                "photo": "photos/1.jpg",
+                "text": ""
+            },
+            {
+                "id": 3,
+                "type": "message",
+                "date": "2024-01-01T08:02:00",
+                "from": "Bob",
+                "from_id": "user456",
+                "forwarded_from": "Carol",
+                "text": "forwarded"
+            },
+            {
+                "id": 4,
+                "type": "service",
+                "date": "2024-01-01T08:03:00",
+                "actor": "Bob",
+                "actor_id": "user456",
+                "action": "pin_message",
+                "text": ""
+            },
+            {
+                "id": 5,
+                "type": "message",
+                "date": "2024-01-01T08:04:00",
+                "from": "Bob",
+                "from_id": "user456",
+                "text": "plain"
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn parse_keeps_text_messages() {
+        let chat = ChatExport::parse(FIXTURE.as_bytes()).unwrap();
+
+        assert_eq!(chat.messages.len(), 2);
+        assert_eq!(chat.messages[0].author_id, 123);
+        assert_eq!(chat.messages[0].date, 1704096000);
+        assert_eq!(chat.messages[1].text, "plain");
+        assert_eq!(chat.messages[1].entities, None);
+        assert_eq!(
+            chat.authors(),
+            vec![(123, "Alice".to_string(), 1), (456, "Bob".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn entities_offsets_in_utf16() {
+        let root: Value = serde_json::from_str(FIXTURE).unwrap();
+        let (text, entities) = message_text(&root["messages"][0]);
+
+        assert_eq!(text, "hi 😀 粗体 and linkhttps://t.me");
+        // the emoji takes two UTF-16 code units
+        assert_eq!(
+            entities,
+            vec![
+                MessageEntity {
+                    kind: MessageEntityKind::Bold,
+                    offset: 6,
+                    length: 2,
+                },
+                MessageEntity {
+                    kind: MessageEntityKind::TextLink {
+                        url: reqwest::Url::parse("https://example.com/").unwrap(),
+                    },
+                    offset: 13,
+                    length: 4,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn date_without_unixtime() {
+        let message: Value = serde_json::from_str(r#"{"date": "2024-01-01T08:00:00"}"#).unwrap();
+
+        assert_eq!(message_date(&message), Some(1704096000));
+    }
+}
@@ -4,20 +4,46 @@ use strfmt::Format;
 use teloxide::{
     prelude::*,
     types::{InlineKeyboardButton, InlineKeyboardMarkup},
-    types::{InlineKeyboardButtonKind, ReplyMarkup},
+    types::{InlineKeyboardButtonKind, ReplyMarkup, User},
     utils::command::{BotCommands, ParseError},
+    utils::markdown::escape,
 };
-use wd_log::log_debug_ln;
+use wd_log::{log_debug_ln, log_warn_ln};
 
 use crate::{
-    db_controller::PaginatedRecordData,
+    callback_commands::page_callback_data,
+    chat_export::{ChatExport, ChatImportReport},
+    db_controller::{ImportSummary, PaginatedRecordData},
+    export::{export, parse_import, ExportFormat, ExportRecord},
+    formatting::{entities_from_json, to_markdown_v2},
     messages::{
-        BOT_ABOUT, BOT_BUTTON_END, BOT_BUTTON_HEAD, BOT_BUTTON_NEXT, BOT_BUTTON_PREV, BOT_HELP,
-        BOT_TEXT_DELETED, BOT_TEXT_LOADING, BOT_TEXT_MUTE_STATUS, BOT_TEXT_NO_LIST,
-        BOT_TEXT_STATUS_OFF, BOT_TEXT_STATUS_ON, BOT_TEXT_WELCOME,
+        BOT_ABOUT, BOT_BUTTON_APPROVE, BOT_BUTTON_CANCEL, BOT_BUTTON_CHAT_AUTHOR,
+        BOT_BUTTON_DELETE_ALL, BOT_BUTTON_END, BOT_BUTTON_HEAD, BOT_BUTTON_IMPORT, BOT_BUTTON_NEXT,
+        BOT_BUTTON_PREV, BOT_BUTTON_REJECT, BOT_BUTTON_UNDO, BOT_HELP,
+        BOT_TEXT_CHAT_IMPORT_AUTHORS, BOT_TEXT_CHAT_IMPORT_EMPTY, BOT_TEXT_CONSENT_NOT_REQUIRED,
+        BOT_TEXT_CONSENT_REQUIRED, BOT_TEXT_CONSENT_STATUS, BOT_TEXT_DELETED, BOT_TEXT_DELETED_IDS,
+        BOT_TEXT_DELETE_ALL_CONFIRM, BOT_TEXT_DEL_USAGE, BOT_TEXT_EXPORT_USAGE,
+        BOT_TEXT_IMPORT_INVALID_FILE, BOT_TEXT_IMPORT_PREVIEW, BOT_TEXT_IMPORT_PRIVATE_ONLY,
+        BOT_TEXT_IMPORT_USAGE, BOT_TEXT_KIND_ANIMATION, BOT_TEXT_KIND_PHOTO, BOT_TEXT_KIND_STICKER,
+        BOT_TEXT_KIND_VIDEO, BOT_TEXT_KIND_VOICE, BOT_TEXT_LOADING, BOT_TEXT_MUTE_STATUS,
+        BOT_TEXT_NOT_DELETED_IDS, BOT_TEXT_NO_LIST, BOT_TEXT_OPT_OUT_STATUS, BOT_TEXT_RECORDED_BY,
+        BOT_TEXT_RECORDING_ALLOWED, BOT_TEXT_RECORDING_REFUSED, BOT_TEXT_RECORD_NOT_FOUND,
+        BOT_TEXT_RESTORED, BOT_TEXT_SCOPE_ALL, BOT_TEXT_SCOPE_GLOBAL, BOT_TEXT_SCOPE_STATUS,
+        BOT_TEXT_SHOULD_START_WITH_AT, BOT_TEXT_STATS_CHAT, BOT_TEXT_STATS_PERSONAL,
+        BOT_TEXT_STATS_QUOTED, BOT_TEXT_STATS_RECORDERS, BOT_TEXT_STATS_TOP_CHAT,
+        BOT_TEXT_STATS_TOP_MINE, BOT_TEXT_STATUS_OFF, BOT_TEXT_STATUS_ON, BOT_TEXT_TAGGED,
+        BOT_TEXT_TAG_USAGE, BOT_TEXT_TRASH, BOT_TEXT_TRASH_EMPTY, BOT_TEXT_TRENDING,
+        BOT_TEXT_WELCOME,
     },
-    telegram_bot::BotServer,
+    search_query::{normalize_tag, split_tags},
+    telegram_bot::{display_username, BotServer},
 };
+use models::prelude::{MediaKind, RecordModel, UserModel};
+
+/// authors of a chat export offered as buttons
+const CHAT_IMPORT_AUTHORS: usize = 20;
+/// ids one `/del` can take, ranges included
+const DEL_IDS_LIMIT: usize = 100;
 
 #[derive(BotCommands, PartialEq, Debug)]
 #[command(rename = "lowercase")]
@@ -35,10 +61,58 @@ pub enum Commands {
     Unmute,
 
     #[command(description = "列出已记录的内容", parse_with = "list_command_parser")]
-    List { username: String },
+    List { username: String, filter: String },
+
+    #[command(description = "列出我记录的内容")]
+    Recorded,
+
+    #[command(description = "随机发送一条记录")]
+    Random { username: String },
+
+    #[command(description = "近期热门的记录")]
+    Trending,
+
+    #[command(description = "统计")]
+    Stats,
+
+    #[command(description = "导出记录", parse_with = "export_command_parser")]
+    Export { format: String, username: String },
+
+    #[command(description = "导入记录")]
+    Import,
+
+    #[command(
+        description = "删除记录，可以写多个id或范围",
+        parse_with = "del_command_parser"
+    )]
+    Del { ids: Vec<i64> },
+
+    #[command(description = "删除自己的全部记录")]
+    Delall,
+
+    #[command(description = "查看回收站")]
+    Trash,
+
+    #[command(description = "从回收站恢复记录")]
+    Restore { id: i64 },
+
+    #[command(description = "设置搜索范围：all 所有所在群组，global 仅全局")]
+    Scope { scope: String },
+
+    #[command(description = "为记录添加标签", parse_with = "tag_command_parser")]
+    Tag { id: i64, tags: String },
+
+    #[command(description = "拒绝被记录")]
+    Optout,
+
+    #[command(description = "允许被记录")]
+    Optin,
+
+    #[command(description = "他人记录的内容需经我同意后公开")]
+    Consent,
 
-    #[command(description = "删除记录")]
-    Del { id: i64 },
+    #[command(description = "他人记录的内容直接公开")]
+    Noconsent,
 
     #[command(description = "注册")]
     Start,
@@ -50,22 +124,135 @@ impl Default for Commands {
     }
 }
 
-fn list_command_parser(input: String) -> Result<(String,), ParseError> {
+fn list_command_parser(input: String) -> Result<(String, String), ParseError> {
     log_debug_ln!(
         "list_command_parse = \"{}\", is empty = {}",
         input,
         input.trim().is_empty()
     );
 
-    let output: String;
+    let input = input.trim();
+    let (username, filter) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+
+    if username.is_empty() || username.starts_with("#") {
+        // no username given, the whole input is the filter
+        Ok(("me".to_string(), input.to_string()))
+    } else {
+        Ok((username.to_string(), filter.trim().to_string()))
+    }
+}
+
+fn export_command_parser(input: String) -> Result<(String, String), ParseError> {
+    let mut format = "json".to_string();
+    let mut username = "me".to_string();
+
+    for word in input.split_whitespace() {
+        if word.starts_with("@") || word == "me" {
+            username = word.to_string();
+        } else {
+            format = word.to_string();
+        }
+    }
+
+    Ok((format, username))
+}
+
+/// ids and inclusive ranges like `3 7 10-15`, repeated ids are dropped.
+fn del_command_parser(input: String) -> Result<(Vec<i64>,), ParseError> {
+    let mut ids: Vec<i64> = vec![];
+
+    for word in input.split_whitespace() {
+        let (start, end) = match word.split_once('-') {
+            Some((start, end)) => (start, end),
+            None => (word, word),
+        };
+        let start = start
+            .parse::<i64>()
+            .map_err(|error| ParseError::IncorrectFormat(error.into()))?;
+        let end = end
+            .parse::<i64>()
+            .map_err(|error| ParseError::IncorrectFormat(error.into()))?;
+        if start > end || (end - start) as usize >= DEL_IDS_LIMIT {
+            return Err(ParseError::IncorrectFormat(
+                format!("invalid range {}", word).into(),
+            ));
+        }
+
+        for id in start..=end {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        if ids.len() > DEL_IDS_LIMIT {
+            return Err(ParseError::IncorrectFormat("too many ids".into()));
+        }
+    }
+
+    if ids.is_empty() {
+        return Err(ParseError::IncorrectFormat("missing id".into()));
+    }
+    Ok((ids,))
+}
+
+fn tag_command_parser(input: String) -> Result<(i64, String), ParseError> {
+    let input = input.trim();
+    let (id, tags) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+    let id = id
+        .parse::<i64>()
+        .map_err(|error| ParseError::IncorrectFormat(error.into()))?;
+
+    Ok((id, tags.trim().to_string()))
+}
+
+fn kind_label(kind: &MediaKind) -> Option<&'static str> {
+    match kind {
+        MediaKind::Text => None,
+        MediaKind::Sticker => Some(BOT_TEXT_KIND_STICKER),
+        MediaKind::Photo => Some(BOT_TEXT_KIND_PHOTO),
+        MediaKind::Animation => Some(BOT_TEXT_KIND_ANIMATION),
+        MediaKind::Voice => Some(BOT_TEXT_KIND_VOICE),
+        MediaKind::Video => Some(BOT_TEXT_KIND_VIDEO),
+    }
+}
+
+/// readable text of a record, media records are prefixed with their kind.
+pub fn record_label(kind: &MediaKind, text: &str) -> String {
+    match kind_label(kind) {
+        None => text.to_string(),
+        Some(label) if text.is_empty() => label.to_string(),
+        Some(label) => format!("{} {}", label, text),
+    }
+}
+
+/// MarkdownV2 text of a record with its original formatting.
+pub fn record_markdown(record: &RecordModel) -> String {
+    let text = to_markdown_v2(
+        &record.message,
+        &entities_from_json(record.entities.as_deref()),
+    );
+
+    match kind_label(&record.kind) {
+        None => text,
+        Some(label) if text.is_empty() => escape(label),
+        Some(label) => format!("{} {}", escape(label), text),
+    }
+}
 
-    if input.trim().is_empty() {
-        output = "me".to_string();
+/// library of the chat a message comes from, `None` for the global one.
+pub fn chat_library(message: &Message) -> Option<i64> {
+    if message.chat.is_private() {
+        None
     } else {
-        output = input
+        Some(message.chat.id.0)
     }
+}
 
-    Ok((output,))
+/// `#tag` list separated by spaces.
+pub fn format_tags(tags: &[String]) -> String {
+    tags.iter()
+        .map(|tag| format!("#{}", tag))
+        .collect::<Vec<String>>()
+        .join(" ")
 }
 
 pub struct CommandHandler {}
@@ -107,7 +294,7 @@ impl CommandHandler {
         }
     }
 
-    pub async fn setup_handler(bot_s: &BotServer, message: &Message) {
+    pub async fn scope_handler(bot_s: &BotServer, message: &Message, scope: &str) {
         let user = match message.from() {
             Some(user) => user,
             None => return,
@@ -118,17 +305,186 @@ impl CommandHandler {
         }
 
         let user_id: i64 = user.id.0.try_into().unwrap();
-        let username = match user.username.to_owned() {
-            Some(username) => format!("@{}", username),
-            None => user.first_name.to_owned(),
+        let search_all = match scope.trim() {
+            "all" => Some(true),
+            "global" => Some(false),
+            _ => None,
+        };
+
+        if let Some(search_all) = search_all {
+            if let Err(error) = bot_s
+                .controller
+                .set_user_search_all(&user_id, search_all)
+                .await
+            {
+                bot_s.controller.err_handler(error);
+            }
+        }
+
+        let search_all = match bot_s.controller.get_user_search_all(&user_id).await {
+            Ok(search_all) => search_all,
+            Err(error) => {
+                bot_s.controller.err_handler(error);
+                return;
+            }
+        };
+
+        let mut vars = HashMap::new();
+        vars.insert(
+            "scope".to_string(),
+            match search_all {
+                true => BOT_TEXT_SCOPE_ALL,
+                false => BOT_TEXT_SCOPE_GLOBAL,
+            },
+        );
+        bot_s
+            .send_text_reply(message, &BOT_TEXT_SCOPE_STATUS.format(&vars).unwrap())
+            .await;
+    }
+
+    pub async fn tag_handler(bot_s: &BotServer, message: &Message, id: i64, tags: &str) {
+        let user = match message.from() {
+            Some(user) => user,
+            None => return,
+        };
+
+        if user.is_bot {
+            return;
+        }
+
+        let mut names: Vec<String> = vec![];
+        for tag in tags.split_whitespace().filter_map(normalize_tag) {
+            if !names.contains(&tag) {
+                names.push(tag);
+            }
+        }
+        let tags = names;
+
+        if tags.is_empty() {
+            bot_s.send_text_reply(message, BOT_TEXT_TAG_USAGE).await;
+            return;
+        }
+
+        match bot_s
+            .controller
+            .add_tags(
+                id,
+                user.id.0.try_into().unwrap(),
+                chat_library(message),
+                &tags,
+            )
+            .await
+        {
+            Ok(true) => {
+                let id = id.to_string();
+                let tags = escape(&format_tags(&tags));
+                let mut vars = HashMap::new();
+                vars.insert("id".to_string(), &id);
+                vars.insert("tags".to_string(), &tags);
+                bot_s
+                    .send_text_reply(message, &BOT_TEXT_TAGGED.format(&vars).unwrap())
+                    .await;
+            }
+            Ok(false) => {
+                bot_s
+                    .send_text_reply(message, BOT_TEXT_RECORD_NOT_FOUND)
+                    .await;
+            }
+            Err(error) => bot_s.controller.err_handler(error),
+        }
+    }
+
+    pub async fn opt_out_handler(bot_s: &BotServer, message: &Message, opt_out: bool) {
+        let user = match message.from() {
+            Some(user) => user,
+            None => return,
+        };
+
+        if user.is_bot {
+            return;
+        }
+
+        if let Err(error) = bot_s
+            .controller
+            .set_user_opt_out(
+                &user.id.0.try_into().unwrap(),
+                &display_username(user),
+                opt_out,
+            )
+            .await
+        {
+            bot_s.controller.err_handler(error);
+            return;
+        }
+
+        let mut vars = HashMap::new();
+        vars.insert(
+            "status".to_string(),
+            match opt_out {
+                true => BOT_TEXT_RECORDING_REFUSED,
+                false => BOT_TEXT_RECORDING_ALLOWED,
+            },
+        );
+        bot_s
+            .send_text_reply(message, &BOT_TEXT_OPT_OUT_STATUS.format(&vars).unwrap())
+            .await;
+    }
+
+    pub async fn consent_handler(bot_s: &BotServer, message: &Message, consent: bool) {
+        let user = match message.from() {
+            Some(user) => user,
+            None => return,
+        };
+
+        if user.is_bot {
+            return;
+        }
+
+        if let Err(error) = bot_s
+            .controller
+            .set_user_consent(
+                &user.id.0.try_into().unwrap(),
+                &display_username(user),
+                consent,
+            )
+            .await
+        {
+            bot_s.controller.err_handler(error);
+            return;
+        }
+
+        let mut vars = HashMap::new();
+        vars.insert(
+            "status".to_string(),
+            match consent {
+                true => BOT_TEXT_CONSENT_REQUIRED,
+                false => BOT_TEXT_CONSENT_NOT_REQUIRED,
+            },
+        );
+        bot_s
+            .send_text_reply(message, &BOT_TEXT_CONSENT_STATUS.format(&vars).unwrap())
+            .await;
+    }
+
+    pub async fn setup_handler(bot_s: &BotServer, message: &Message) {
+        let user = match message.from() {
+            Some(user) => user,
+            None => return,
         };
+
+        if user.is_bot {
+            return;
+        }
+
+        let user_id: i64 = user.id.0.try_into().unwrap();
+        let username = display_username(user);
         if let Err(error) = bot_s.controller.register_user(&user_id, &username).await {
             bot_s.controller.err_handler(error);
         }
         bot_s.send_text_reply(message, BOT_TEXT_WELCOME).await;
     }
 
-    pub async fn del_handler(bot_s: &BotServer, message: &Message, id: i64) {
+    pub async fn del_handler(bot_s: &BotServer, message: &Message, ids: &[i64]) {
         let user = match message.from() {
             Some(user) => user,
             None => return,
@@ -138,18 +494,86 @@ impl CommandHandler {
             return;
         }
 
-        if let Err(error) = bot_s
+        let id = match ids {
+            [id] => *id,
+            _ => return Self::del_many_handler(bot_s, message, user, ids).await,
+        };
+
+        match bot_s
             .controller
             .del_record(id, user.id.0.try_into().unwrap())
             .await
         {
-            bot_s.controller.err_handler(error);
+            Ok(true) => {
+                bot_s
+                    .send_text_reply_with_inline_key(
+                        message,
+                        BOT_TEXT_DELETED,
+                        Self::generate_undo_keyboard(id),
+                    )
+                    .await;
+            }
+            Ok(false) => {
+                bot_s
+                    .send_text_reply(message, BOT_TEXT_RECORD_NOT_FOUND)
+                    .await;
+            }
+            Err(error) => bot_s.controller.err_handler(error),
+        }
+    }
+
+    /// delete several records, reporting which were deleted and which were not.
+    async fn del_many_handler(bot_s: &BotServer, message: &Message, user: &User, ids: &[i64]) {
+        let deleted = match bot_s
+            .controller
+            .del_records(ids, user.id.0.try_into().unwrap())
+            .await
+        {
+            Ok(deleted) => deleted,
+            Err(error) => {
+                bot_s.controller.err_handler(error);
+                return;
+            }
+        };
+        let not_deleted: Vec<i64> = ids
+            .iter()
+            .copied()
+            .filter(|id| !deleted.contains(id))
+            .collect();
+
+        let mut msg = String::new();
+        for (template, ids) in [
+            (BOT_TEXT_DELETED_IDS, &deleted),
+            (BOT_TEXT_NOT_DELETED_IDS, &not_deleted),
+        ] {
+            if ids.is_empty() {
+                continue;
+            }
+            let mut vars = HashMap::new();
+            vars.insert(
+                "ids".to_string(),
+                ids.iter()
+                    .map(|id| id.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            );
+            msg.push_str(&template.format(&vars).unwrap());
         }
 
-        bot_s.send_text_reply(message, BOT_TEXT_DELETED).await;
+        bot_s.send_text_reply(message, &msg).await;
+    }
+
+    /// reply the usage of `/del` when its ids can't be parsed.
+    pub async fn del_usage_handler(bot_s: &BotServer, message: &Message) {
+        let mut vars = HashMap::new();
+        vars.insert("limit".to_string(), DEL_IDS_LIMIT.to_string());
+        bot_s
+            .send_text_reply(message, &BOT_TEXT_DEL_USAGE.format(&vars).unwrap())
+            .await;
     }
 
-    pub async fn list_handler(bot_s: &BotServer, message: &Message, username: &str, page: usize) {
+    /// ask for confirmation before deleting all records of the user.
+    pub async fn del_all_handler(bot_s: &BotServer, message: &Message) {
         let user = match message.from() {
             Some(user) => user,
             None => return,
@@ -159,16 +583,128 @@ impl CommandHandler {
             return;
         }
 
-        let msg_id = match bot_s.send_text_reply(message, BOT_TEXT_LOADING).await {
-            Some(id) => id,
+        let count = match bot_s
+            .controller
+            .count_user_records(user.id.0.try_into().unwrap())
+            .await
+        {
+            Ok(count) => count,
+            Err(error) => {
+                bot_s.controller.err_handler(error);
+                return;
+            }
+        };
+
+        if count == 0 {
+            bot_s.send_text_reply(message, BOT_TEXT_NO_LIST).await;
+            return;
+        }
+
+        let mut vars = HashMap::new();
+        vars.insert("count".to_string(), count.to_string());
+        vars.insert("days".to_string(), bot_s.trash_days.to_string());
+        // the confirmation checks who clicks against the message this replies to
+        bot_s
+            .send_text_reply_with_inline_key(
+                message,
+                &BOT_TEXT_DELETE_ALL_CONFIRM.format(&vars).unwrap(),
+                Self::generate_delete_all_keyboard(),
+            )
+            .await;
+    }
+
+    pub async fn restore_handler(bot_s: &BotServer, message: &Message, id: i64) {
+        let user = match message.from() {
+            Some(user) => user,
             None => return,
         };
 
-        let (msg, markup) = match Self::record_msg_genrator(bot_s, message, username, page).await {
-            Some(d) => d,
+        if user.is_bot {
+            return;
+        }
+
+        match bot_s
+            .controller
+            .restore_record(id, user.id.0.try_into().unwrap())
+            .await
+        {
+            Ok(true) => {
+                bot_s.send_text_reply(message, BOT_TEXT_RESTORED).await;
+            }
+            Ok(false) => {
+                bot_s
+                    .send_text_reply(message, BOT_TEXT_RECORD_NOT_FOUND)
+                    .await;
+            }
+            Err(error) => bot_s.controller.err_handler(error),
+        }
+    }
+
+    pub async fn trash_handler(bot_s: &BotServer, message: &Message) {
+        let user = match message.from() {
+            Some(user) => user,
+            None => return,
+        };
+
+        if user.is_bot {
+            return;
+        }
+
+        let records = match bot_s
+            .controller
+            .get_trashed_records(user.id.0.try_into().unwrap())
+            .await
+        {
+            Ok(records) => records,
+            Err(error) => {
+                bot_s.controller.err_handler(error);
+                return;
+            }
+        };
+
+        if records.is_empty() {
+            bot_s.send_text_reply(message, BOT_TEXT_TRASH_EMPTY).await;
+            return;
+        }
+
+        let mut vars = HashMap::new();
+        vars.insert("days".to_string(), bot_s.trash_days.to_string());
+        let mut msg = BOT_TEXT_TRASH.format(&vars).unwrap();
+        for record in records.iter() {
+            msg = format!("{}`{}`\t\t{}\n", msg, record.id, record_markdown(record));
+        }
+
+        bot_s.send_text_reply(message, &msg).await;
+    }
+
+    pub async fn list_handler(
+        bot_s: &BotServer,
+        message: &Message,
+        username: &str,
+        filter: &str,
+        page: usize,
+    ) {
+        let user = match message.from() {
+            Some(user) => user,
+            None => return,
+        };
+
+        if user.is_bot {
+            return;
+        }
+
+        let msg_id = match bot_s.send_text_reply(message, BOT_TEXT_LOADING).await {
+            Some(id) => id,
             None => return,
         };
 
+        let viewer = user.id.0.try_into().unwrap();
+        let (msg, markup) =
+            match Self::record_msg_genrator(bot_s, message, viewer, username, filter, page).await {
+                Some(d) => d,
+                None => return,
+            };
+
         bot_s
             .edit_text_reply_with_inline_key(message, msg_id, msg.as_str(), markup)
             .await;
@@ -177,7 +713,9 @@ impl CommandHandler {
     pub async fn record_msg_genrator(
         bot_s: &BotServer,
         message: &Message,
+        viewer: i64,
         username: &str,
+        filter: &str,
         page: usize,
     ) -> Option<(String, ReplyMarkup)> {
         let someone = match bot_s.controller.get_user_by_username(username).await {
@@ -193,9 +731,17 @@ impl CommandHandler {
             None => return None,
         };
 
+        let (tags, keywords) = split_tags(filter);
         let data = match bot_s
             .controller
-            .get_records_by_userid_with_pagination(someone.tg_uid, page)
+            .get_records_by_userid_with_pagination(
+                someone.tg_uid,
+                page,
+                &tags,
+                &keywords,
+                viewer,
+                chat_library(message),
+            )
             .await
         {
             Ok(data) => data,
@@ -211,97 +757,472 @@ impl CommandHandler {
         };
 
         Some((
-            Self::generate_text_record_msg(&paginated_record_data, page),
-            Self::generate_inline_keyboard(
-                page,
-                paginated_record_data.pages_count,
-                username,
-                message,
-            ),
+            Self::generate_text_record_msg(&paginated_record_data, page, false),
+            Self::generate_inline_keyboard(page, paginated_record_data.pages_count, |page| {
+                page_callback_data(message.id, username, page, !filter.is_empty())
+            }),
         ))
     }
 
-    fn generate_inline_keyboard(
-        page: usize,
-        pages_count: usize,
-        username: &str,
-        message: &Message,
+    pub async fn random_handler(bot_s: &BotServer, message: &Message, username: &str) {
+        let user = match message.from() {
+            Some(user) => user,
+            None => return,
+        };
+
+        if user.is_bot {
+            return;
+        }
+
+        let author = if username.is_empty() {
+            None
+        } else if !username.starts_with("@") {
+            bot_s
+                .send_text_reply(message, BOT_TEXT_SHOULD_START_WITH_AT)
+                .await;
+            return;
+        } else {
+            match bot_s.controller.get_user_by_username(username).await {
+                Ok(Some(author)) => Some(author.tg_uid),
+                Ok(None) => {
+                    bot_s.send_text_reply(message, BOT_TEXT_NO_LIST).await;
+                    return;
+                }
+                Err(error) => {
+                    bot_s.controller.err_handler(error);
+                    return;
+                }
+            }
+        };
+
+        match bot_s
+            .controller
+            .get_random_record(author, user.id.0.try_into().unwrap(), chat_library(message))
+            .await
+        {
+            Ok(Some((record, author))) => {
+                let username = author
+                    .and_then(|author| author.username)
+                    .unwrap_or_default();
+                bot_s.send_record(message, &record, &username).await;
+            }
+            Ok(None) => {
+                bot_s.send_text_reply(message, BOT_TEXT_NO_LIST).await;
+            }
+            Err(error) => bot_s.controller.err_handler(error),
+        }
+    }
+
+    pub async fn trending_handler(bot_s: &BotServer, message: &Message) {
+        let user = match message.from() {
+            Some(user) => user,
+            None => return,
+        };
+
+        if user.is_bot {
+            return;
+        }
+
+        let records = match bot_s
+            .controller
+            .get_trending_records(user.id.0.try_into().unwrap(), chat_library(message))
+            .await
+        {
+            Ok(records) => records,
+            Err(error) => {
+                bot_s.controller.err_handler(error);
+                return;
+            }
+        };
+
+        if records.is_empty() {
+            bot_s.send_text_reply(message, BOT_TEXT_NO_LIST).await;
+            return;
+        }
+
+        let mut msg = BOT_TEXT_TRENDING.to_string();
+        for (record, author) in records.iter() {
+            let author = match author {
+                Some(UserModel {
+                    username: Some(username),
+                    ..
+                }) => format!("{}: ", escape(username)),
+                _ => String::new(),
+            };
+            msg = format!(
+                "{}`{}`\t\t{}{}\n",
+                msg,
+                record.id,
+                author,
+                record_markdown(record)
+            );
+        }
+
+        bot_s.send_text_reply(message, &msg).await;
+    }
+
+    pub async fn stats_handler(bot_s: &BotServer, message: &Message) {
+        let user = match message.from() {
+            Some(user) => user,
+            None => return,
+        };
+
+        if user.is_bot {
+            return;
+        }
+
+        let chat_id = chat_library(message);
+        let stats = match bot_s
+            .controller
+            .get_stats(user.id.0.try_into().unwrap(), chat_id)
+            .await
+        {
+            Ok(stats) => stats,
+            Err(error) => {
+                bot_s.controller.err_handler(error);
+                return;
+            }
+        };
+
+        let mut vars = HashMap::new();
+        vars.insert("total".to_string(), stats.total.to_string());
+        vars.insert("mine".to_string(), stats.mine.to_string());
+        let (mut msg, top_title) = match chat_id {
+            Some(_) => (
+                BOT_TEXT_STATS_CHAT.format(&vars).unwrap(),
+                BOT_TEXT_STATS_TOP_CHAT,
+            ),
+            None => (
+                BOT_TEXT_STATS_PERSONAL.format(&vars).unwrap(),
+                BOT_TEXT_STATS_TOP_MINE,
+            ),
+        };
+
+        if !stats.top_records.is_empty() {
+            msg.push_str(top_title);
+            for record in stats.top_records.iter() {
+                msg = format!(
+                    "{}`{}`\t\t{} 🔥{}\n",
+                    msg,
+                    record.id,
+                    record_markdown(record),
+                    record.hot
+                );
+            }
+        }
+        for (title, users) in [
+            (BOT_TEXT_STATS_QUOTED, &stats.top_quoted),
+            (BOT_TEXT_STATS_RECORDERS, &stats.top_recorders),
+        ] {
+            if users.is_empty() {
+                continue;
+            }
+            msg.push_str(title);
+            for (username, count) in users.iter() {
+                msg = format!("{}`{}`\t\t{}\n", msg, count, escape(username));
+            }
+        }
+
+        bot_s.send_text_reply(message, &msg).await;
+    }
+
+    pub async fn export_handler(
+        bot_s: &BotServer,
+        message: &Message,
+        format: &str,
+        username: &str,
+    ) {
+        let user = match message.from() {
+            Some(user) => user,
+            None => return,
+        };
+
+        if user.is_bot {
+            return;
+        }
+
+        let format = match format.parse::<ExportFormat>() {
+            Ok(format) => format,
+            Err(_) => {
+                bot_s.send_text_reply(message, BOT_TEXT_EXPORT_USAGE).await;
+                return;
+            }
+        };
+
+        let viewer: i64 = user.id.0.try_into().unwrap();
+        let author = if username == "me" {
+            viewer
+        } else if !username.starts_with("@") {
+            bot_s
+                .send_text_reply(message, BOT_TEXT_SHOULD_START_WITH_AT)
+                .await;
+            return;
+        } else {
+            match bot_s.controller.get_user_by_username(username).await {
+                Ok(Some(author)) => author.tg_uid,
+                Ok(None) => {
+                    bot_s.send_text_reply(message, BOT_TEXT_NO_LIST).await;
+                    return;
+                }
+                Err(error) => {
+                    bot_s.controller.err_handler(error);
+                    return;
+                }
+            }
+        };
+
+        let (records, tags) = match bot_s
+            .controller
+            .get_records_for_export(author, viewer, chat_library(message))
+            .await
+        {
+            Ok(Some(data)) => data,
+            Ok(None) => {
+                bot_s.send_text_reply(message, BOT_TEXT_NO_LIST).await;
+                return;
+            }
+            Err(error) => {
+                bot_s.controller.err_handler(error);
+                return;
+            }
+        };
+
+        if records.is_empty() {
+            bot_s.send_text_reply(message, BOT_TEXT_NO_LIST).await;
+            return;
+        }
+
+        let records: Vec<ExportRecord> = records
+            .into_iter()
+            .map(|(record, author)| {
+                let tags = tags.get(&record.id).cloned().unwrap_or_default();
+                ExportRecord::new(record, author, &tags)
+            })
+            .collect();
+
+        match export(&records, format) {
+            Ok(file) => {
+                bot_s
+                    .send_document_reply(message, format!("records.{}", format.extension()), file)
+                    .await;
+            }
+            Err(error) => log_warn_ln!("cannot export records: {}", error),
+        }
+    }
+
+    /// dry run of importing a document, the import is done on confirmation.
+    pub async fn import_handler(bot_s: &BotServer, message: &Message) {
+        let user = match message.from() {
+            Some(user) => user,
+            None => return,
+        };
+
+        if user.is_bot {
+            return;
+        }
+
+        if !message.chat.is_private() {
+            bot_s
+                .send_text_reply(message, BOT_TEXT_IMPORT_PRIVATE_ONLY)
+                .await;
+            return;
+        }
+
+        // the document itself, or the one `/import` replies to
+        let document_message = if message.document().is_some() {
+            message
+        } else {
+            match message.reply_to_message() {
+                Some(reply) if reply.document().is_some() => reply,
+                _ => {
+                    bot_s.send_text_reply(message, BOT_TEXT_IMPORT_USAGE).await;
+                    return;
+                }
+            }
+        };
+
+        let data = match bot_s.download_document(document_message).await {
+            Some(data) => data,
+            None => {
+                bot_s
+                    .send_text_reply(message, BOT_TEXT_IMPORT_INVALID_FILE)
+                    .await;
+                return;
+            }
+        };
+
+        // chat exports of Telegram Desktop import the messages of picked authors
+        if let Ok(chat) = ChatExport::parse(&data) {
+            if chat.messages.is_empty() {
+                bot_s
+                    .send_text_reply(message, BOT_TEXT_CHAT_IMPORT_EMPTY)
+                    .await;
+                return;
+            }
+
+            let mut vars = HashMap::new();
+            vars.insert("total".to_string(), chat.messages.len().to_string());
+            bot_s
+                .send_text_reply_with_inline_key(
+                    document_message,
+                    &BOT_TEXT_CHAT_IMPORT_AUTHORS.format(&vars).unwrap(),
+                    Self::generate_chat_import_keyboard(&chat),
+                )
+                .await;
+            return;
+        }
+
+        let file_name = document_message
+            .document()
+            .and_then(|document| document.file_name.as_deref());
+        let records = match parse_import(file_name, &data) {
+            Ok(records) => records,
+            Err(error) => {
+                log_warn_ln!("cannot parse import file: {}", error);
+                bot_s
+                    .send_text_reply(message, BOT_TEXT_IMPORT_INVALID_FILE)
+                    .await;
+                return;
+            }
+        };
+
+        let summary = match bot_s
+            .controller
+            .import_records(
+                user.id.0.try_into().unwrap(),
+                &display_username(user),
+                records,
+                true,
+            )
+            .await
+        {
+            Ok(summary) => summary,
+            Err(error) => {
+                bot_s.controller.err_handler(error);
+                return;
+            }
+        };
+
+        // the confirmation finds the document by the message this replies to
+        bot_s
+            .send_text_reply_with_inline_key(
+                document_message,
+                &Self::import_summary_msg(BOT_TEXT_IMPORT_PREVIEW, &summary),
+                Self::generate_import_keyboard(),
+            )
+            .await;
+    }
+
+    pub fn chat_import_report_msg(template: &str, name: &str, report: &ChatImportReport) -> String {
+        let mut vars = HashMap::new();
+        vars.insert("name".to_string(), escape(name));
+        vars.insert("done".to_string(), report.done().to_string());
+        vars.insert("total".to_string(), report.total.to_string());
+        vars.insert("added".to_string(), report.added.to_string());
+        vars.insert("duplicate".to_string(), report.duplicate.to_string());
+        vars.insert("opted_out".to_string(), report.opted_out.to_string());
+        template.format(&vars).unwrap()
+    }
+
+    pub fn import_summary_msg(template: &str, summary: &ImportSummary) -> String {
+        let mut vars = HashMap::new();
+        vars.insert("added".to_string(), summary.added.to_string());
+        vars.insert("duplicate".to_string(), summary.duplicate.to_string());
+        vars.insert("invalid".to_string(), summary.invalid.to_string());
+        template.format(&vars).unwrap()
+    }
+
+    pub async fn recorded_handler(bot_s: &BotServer, message: &Message) {
+        let user = match message.from() {
+            Some(user) => user,
+            None => return,
+        };
+
+        if user.is_bot {
+            return;
+        }
+
+        let msg_id = match bot_s.send_text_reply(message, BOT_TEXT_LOADING).await {
+            Some(id) => id,
+            None => return,
+        };
+
+        let recorder = user.id.0.try_into().unwrap();
+        let (msg, markup) =
+            match Self::recorded_msg_genrator(bot_s, message, recorder, recorder, 0).await {
+                Some(d) => d,
+                None => return,
+            };
+
+        bot_s
+            .edit_text_reply_with_inline_key(message, msg_id, msg.as_str(), markup)
+            .await;
+    }
+
+    pub async fn recorded_msg_genrator(
+        bot_s: &BotServer,
+        message: &Message,
+        recorder: i64,
+        viewer: i64,
+        page: usize,
+    ) -> Option<(String, ReplyMarkup)> {
+        let data = match bot_s
+            .controller
+            .get_records_by_recorder_with_pagination(recorder, page, viewer, chat_library(message))
+            .await
+        {
+            Ok(data) => data,
+            Err(error) => {
+                bot_s.controller.err_handler(error);
+                return None;
+            }
+        };
+
+        let paginated_record_data = match data {
+            Some(d) => d,
+            None => {
+                // never recorded anything
+                return Some((
+                    BOT_TEXT_NO_LIST.to_string(),
+                    Self::generate_inline_keyboard(0, 0, |_| String::new()),
+                ));
+            }
+        };
+
+        Some((
+            Self::generate_text_record_msg(&paginated_record_data, page, true),
+            Self::generate_inline_keyboard(page, paginated_record_data.pages_count, |page| {
+                format!("!recorded {} {}", recorder, page)
+            }),
+        ))
+    }
+
+    /// paging buttons, `callback_data` builds the callback data of a page.
+    fn generate_inline_keyboard(
+        page: usize,
+        pages_count: usize,
+        callback_data: impl Fn(usize) -> String,
     ) -> ReplyMarkup {
+        let button = |text: &str, page: usize| InlineKeyboardButton {
+            text: text.to_string(),
+            kind: InlineKeyboardButtonKind::CallbackData(callback_data(page)),
+        };
+
         let inline_keyboards = match page {
             page if page == 0 && pages_count > 1 => vec![
-                InlineKeyboardButton {
-                    text: BOT_BUTTON_NEXT.to_string(),
-                    kind: InlineKeyboardButtonKind::CallbackData(format!(
-                        "!page {} {} {}",
-                        message.id,
-                        username,
-                        page + 1
-                    )),
-                },
-                InlineKeyboardButton {
-                    text: BOT_BUTTON_END.to_string(),
-                    kind: InlineKeyboardButtonKind::CallbackData(format!(
-                        "!page {} {} {}",
-                        message.id,
-                        username,
-                        pages_count - 1
-                    )),
-                },
+                button(BOT_BUTTON_NEXT, page + 1),
+                button(BOT_BUTTON_END, pages_count - 1),
             ],
             page if page == 0 && pages_count <= 1 => vec![],
             page if page >= pages_count - 1 => vec![
-                InlineKeyboardButton {
-                    text: BOT_BUTTON_HEAD.to_string(),
-                    kind: InlineKeyboardButtonKind::CallbackData(format!(
-                        "!page {} {} {}",
-                        message.id, username, 0
-                    )),
-                },
-                InlineKeyboardButton {
-                    text: BOT_BUTTON_PREV.to_string(),
-                    kind: InlineKeyboardButtonKind::CallbackData(format!(
-                        "!page {} {} {}",
-                        message.id,
-                        username,
-                        page - 1
-                    )),
-                },
+                button(BOT_BUTTON_HEAD, 0),
+                button(BOT_BUTTON_PREV, page - 1),
             ],
             _ => vec![
-                InlineKeyboardButton {
-                    text: BOT_BUTTON_HEAD.to_string(),
-                    kind: InlineKeyboardButtonKind::CallbackData(format!(
-                        "!page {} {} {}",
-                        message.id, username, 0
-                    )),
-                },
-                InlineKeyboardButton {
-                    text: BOT_BUTTON_PREV.to_string(),
-                    kind: InlineKeyboardButtonKind::CallbackData(format!(
-                        "!page {} {} {}",
-                        message.id,
-                        username,
-                        page - 1
-                    )),
-                },
-                InlineKeyboardButton {
-                    text: BOT_BUTTON_NEXT.to_string(),
-                    kind: InlineKeyboardButtonKind::CallbackData(format!(
-                        "!page {} {} {}",
-                        message.id,
-                        username,
-                        page + 1
-                    )),
-                },
-                InlineKeyboardButton {
-                    text: BOT_BUTTON_END.to_string(),
-                    kind: InlineKeyboardButtonKind::CallbackData(format!(
-                        "!page {} {} {}",
-                        message.id,
-                        username,
-                        pages_count - 1
-                    )),
-                },
+                button(BOT_BUTTON_HEAD, 0),
+                button(BOT_BUTTON_PREV, page - 1),
+                button(BOT_BUTTON_NEXT, page + 1),
+                button(BOT_BUTTON_END, pages_count - 1),
             ],
         };
 
@@ -310,20 +1231,126 @@ impl CommandHandler {
         })
     }
 
+    /// approve / reject buttons of a pending record notice.
+    pub fn generate_review_keyboard(id: i64) -> ReplyMarkup {
+        let button = |text: &str, command: &str| InlineKeyboardButton {
+            text: text.to_string(),
+            kind: InlineKeyboardButtonKind::CallbackData(format!("!{} {}", command, id)),
+        };
+
+        ReplyMarkup::InlineKeyboard(InlineKeyboardMarkup {
+            inline_keyboard: vec![vec![
+                button(BOT_BUTTON_APPROVE, "approve"),
+                button(BOT_BUTTON_REJECT, "reject"),
+            ]],
+        })
+    }
+
+    /// confirm and cancel buttons of an import preview.
+    fn generate_import_keyboard() -> ReplyMarkup {
+        let button = |text: &str, confirm: bool| InlineKeyboardButton {
+            text: text.to_string(),
+            kind: InlineKeyboardButtonKind::CallbackData(format!("!import {}", confirm)),
+        };
+
+        ReplyMarkup::InlineKeyboard(InlineKeyboardMarkup {
+            inline_keyboard: vec![vec![
+                button(BOT_BUTTON_IMPORT, true),
+                button(BOT_BUTTON_CANCEL, false),
+            ]],
+        })
+    }
+
+    /// a button for each author of a chat export, most messages first.
+    pub fn generate_chat_import_keyboard(chat: &ChatExport) -> ReplyMarkup {
+        let buttons = chat
+            .authors()
+            .into_iter()
+            .take(CHAT_IMPORT_AUTHORS)
+            .map(|(id, name, count)| {
+                let mut vars = HashMap::new();
+                vars.insert("name".to_string(), name);
+                vars.insert("count".to_string(), count.to_string());
+                vec![InlineKeyboardButton {
+                    text: BOT_BUTTON_CHAT_AUTHOR.format(&vars).unwrap(),
+                    kind: InlineKeyboardButtonKind::CallbackData(format!("!chatimport {}", id)),
+                }]
+            })
+            .collect();
+
+        ReplyMarkup::InlineKeyboard(InlineKeyboardMarkup {
+            inline_keyboard: buttons,
+        })
+    }
+
+    /// confirm and cancel buttons of `/delall`.
+    fn generate_delete_all_keyboard() -> ReplyMarkup {
+        let button = |text: &str, command: &str| InlineKeyboardButton {
+            text: text.to_string(),
+            kind: InlineKeyboardButtonKind::CallbackData(format!("!{}", command)),
+        };
+
+        ReplyMarkup::InlineKeyboard(InlineKeyboardMarkup {
+            inline_keyboard: vec![vec![
+                button(BOT_BUTTON_DELETE_ALL, "deleteall"),
+                button(BOT_BUTTON_CANCEL, "canceldeleteall"),
+            ]],
+        })
+    }
+
+    /// undo button of a deleted record.
+    fn generate_undo_keyboard(id: i64) -> ReplyMarkup {
+        ReplyMarkup::InlineKeyboard(InlineKeyboardMarkup {
+            inline_keyboard: vec![vec![InlineKeyboardButton {
+                text: BOT_BUTTON_UNDO.to_string(),
+                kind: InlineKeyboardButtonKind::CallbackData(format!("!undo {}", id)),
+            }]],
+        })
+    }
+
+    /// `show_author` prefixes each record with whose words it is.
     fn generate_text_record_msg(
         paginated_record_data: &PaginatedRecordData,
         page: usize,
+        show_author: bool,
     ) -> String {
-        let mut msg = String::from("```");
+        let mut msg = String::new();
 
-        for (message, _) in paginated_record_data.current_data.iter() {
-            msg = format!("{}\n{}\t\t\t\t{}", msg, message.id, message.message);
+        for (record, author) in paginated_record_data.current_data.iter() {
+            let tags = match paginated_record_data.tags.get(&record.id) {
+                Some(tags) => format!(" {}", escape(&format_tags(tags))),
+                None => String::new(),
+            };
+            let author = match author {
+                Some(UserModel {
+                    username: Some(username),
+                    ..
+                }) if show_author => format!("{}: ", escape(username)),
+                _ => String::new(),
+            };
+            let recorder = match paginated_record_data.recorders.get(&record.id) {
+                Some(recorder) => {
+                    let mut vars = HashMap::new();
+                    vars.insert("recorder".to_string(), escape(recorder));
+                    BOT_TEXT_RECORDED_BY.format(&vars).unwrap()
+                }
+                None => String::new(),
+            };
+            msg = format!(
+                "{}`{}`\t\t{}{}{}{}\n",
+                msg,
+                record.id,
+                author,
+                record_markdown(record),
+                tags,
+                recorder
+            );
         }
         if paginated_record_data.items_count == 0 {
             msg = BOT_TEXT_NO_LIST.to_string();
         } else {
             msg = format!(
-                "{}\n```\n{}/{}",
+                "{}\n{}/{}",
                 msg,
                 page + 1,
                 paginated_record_data.pages_count
@@ -333,3 +1360,49 @@ impl CommandHandler {
         msg
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn del_ids_and_ranges() {
+        assert_eq!(
+            del_command_parser("3 7 10-12".to_string()).unwrap(),
+            (vec![3, 7, 10, 11, 12],)
+        );
+    }
+
+    #[test]
+    fn del_repeated_ids_once() {
+        assert_eq!(
+            del_command_parser("5 3-6 4-8".to_string()).unwrap(),
+            (vec![5, 3, 4, 6, 7, 8],)
+        );
+    }
+
+    #[test]
+    fn del_invalid_ranges() {
+        for input in ["15-10", "-5", "5-", "abc", "1-2-3", ""] {
+            assert!(
+                del_command_parser(input.to_string()).is_err(),
+                "{} should not parse",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn del_ids_limit() {
+        assert_eq!(
+            del_command_parser(format!("1-{}", DEL_IDS_LIMIT))
+                .unwrap()
+                .0
+                .len(),
+            DEL_IDS_LIMIT
+        );
+        assert!(del_command_parser(format!("1-{}", DEL_IDS_LIMIT + 1)).is_err());
+        assert!(del_command_parser(format!("1-{} 500", DEL_IDS_LIMIT)).is_err());
+        assert!(del_command_parser("10-500".to_string()).is_err());
+    }
+}
@@ -1,7 +1,10 @@
-use clap::Parser;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
 
 const DEFAULT_DATABASE: &'static str = "sqlite:///saysthbot.db";
 const DEFAULT_API_URL: &'static str = "https://api.telegram.org";
+const DEFAULT_TRASH_DAYS: u64 = 30;
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
@@ -10,9 +13,9 @@ pub struct Args {
     #[clap(short = 'D', long, value_parser, default_value_t = false)]
     pub debug: bool,
 
-    /// Telegram bot token
+    /// Telegram bot token, not needed by subcommands
     #[clap(short, long, value_parser, env = "TGBOT_TOKEN")]
-    pub tgbot_token: String,
+    pub tgbot_token: Option<String>,
 
     /// Database URI
     #[clap(short, long, value_parser, env = "DATABASE_URI", default_value=DEFAULT_DATABASE)]
@@ -21,4 +24,30 @@ pub struct Args {
     /// Api Server URL
     #[clap(long, value_parser, env = "API_URL", default_value=DEFAULT_API_URL)]
     pub api_url: String,
+
+    /// Days before deleted records are purged from trash
+    #[clap(long, value_parser, env = "TRASH_DAYS", default_value_t = DEFAULT_TRASH_DAYS)]
+    pub trash_days: u64,
+
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Import messages of a Telegram Desktop chat export as records, lists
+    /// the authors if none is given
+    ImportChat {
+        /// Path of result.json
+        #[clap(value_parser)]
+        file: PathBuf,
+
+        /// Telegram user ID of an author whose messages are imported
+        #[clap(short, long = "author", value_parser)]
+        authors: Vec<i64>,
+
+        /// Telegram user ID recorded as who recorded the messages
+        #[clap(short, long, value_parser)]
+        recorder: i64,
+    },
 }
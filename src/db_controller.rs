@@ -1,22 +1,163 @@
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::export::ExportRecord;
+use crate::search_query::{HotFilter, SearchQuery};
+use crate::search_text::{initials, normalize, pinyin, pinyin_query, trigram_similarity};
 use migration::{Migrator, MigratorTrait};
 use models::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, Database, DatabaseConnection, DatabaseTransaction, DbErr,
-    EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, Set, TransactionTrait,
+    sea_query::{Alias, Expr, JoinType, Query},
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, Database, DatabaseConnection,
+    DatabaseTransaction, DbBackend, DbErr, EntityTrait, FromQueryResult, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, Select, Set, Statement, TransactionTrait,
 };
 use wd_log::{log_error_ln, log_info_ln, log_panic, log_warn_ln};
 
 const PAGE_SIZE: usize = 25;
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+/// inline query results are limited to 50 by Telegram
+const INLINE_RESULTS: usize = 50;
+/// trending records on the first page of an empty inline query
+const HOT_RESULTS: u64 = 30;
+/// random records mixed into them
+const RANDOM_RESULTS: usize = 20;
+/// random ids tried for each random record wanted
+const RANDOM_PICKS: usize = 4;
+/// the first page of an empty inline query stays the same this long, so
+/// later pages can leave its records out
+const RANDOM_SEED_PERIOD: i64 = 10 * 60;
+/// matches of each kind ranked together with trend
+const SEARCH_CANDIDATES: u64 = 1000;
+/// share of trend in the ranking score, the rest is relevance
+const HOT_WEIGHT: f64 = 0.3;
+/// relevance of a pinyin match, a full-text match is up to 1
+const PINYIN_RELEVANCE: f64 = 0.8;
+/// relevance of a pinyin initials match
+const INITIALS_RELEVANCE: f64 = 0.6;
+const BACKFILL_BATCH: u64 = 500;
+/// least trigram similarity of a fuzzy match
+const FUZZY_THRESHOLD: f64 = 0.4;
+/// trending records compared with the query when the database can't do it
+const FUZZY_CANDIDATES: u64 = 2000;
+/// a use counts half in trend after this long
+const TREND_HALF_LIFE: i64 = 7 * SECONDS_PER_DAY;
+/// records listed by `/trending`
+const TRENDING_RESULTS: u64 = 10;
+/// share of the viewer's own usage added to the ranking score
+const USAGE_WEIGHT: f64 = 0.3;
+/// a use counts half in the viewer's own usage after this long
+const USAGE_HALF_LIFE: i64 = 30 * SECONDS_PER_DAY;
+/// records the viewer used lately on the first page of an empty inline query
+const USAGE_RESULTS: usize = 10;
+/// latest usages of the viewer they are picked from
+const USAGE_CANDIDATES: u64 = 100;
+/// entries of each ranking in `/stats`
+const STATS_TOP: u64 = 5;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Controller {
     db: DatabaseConnection,
 }
 
+/// message content to be recorded
+pub struct RecordContent {
+    pub kind: MediaKind,
+    pub text: String,
+    pub entities: Option<String>,
+    pub file_id: Option<String>,
+    pub file_unique_id: String,
+    /// unix time of the original message, `None` for now
+    pub created_at: Option<i64>,
+}
+
+pub enum AddRecordResult {
+    /// the record is saved
+    Added(RecordModel),
+    /// the quoted user refused to be recorded
+    OptedOut,
+    /// the same content of the same user is already recorded
+    Duplicate(RecordModel),
+}
+
+#[derive(Debug, FromQueryResult)]
+struct RecordId {
+    id: i64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct IdRange {
+    min: Option<i64>,
+    max: Option<i64>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct RecordHot {
+    id: i64,
+    hot: i64,
+    trend: f64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct RankedRecord {
+    id: i64,
+    relevance: f64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct UserRecords {
+    user_id: i64,
+    records: i64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct NormalizedRecord {
+    id: i64,
+    normalized: Option<String>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct SpelledRecord {
+    id: i64,
+    pinyin: Option<String>,
+}
+
 pub struct PaginatedRecordData {
     pub items_count: usize,
     pub pages_count: usize,
     pub current_data: Vec<(RecordModel, Option<UserModel>)>,
+    /// tag names by record id
+    pub tags: HashMap<i64, Vec<String>>,
+    /// who recorded the record by record id, only for the quoted user
+    pub recorders: HashMap<i64, String>,
+}
+
+/// what an `/import` did, or would do on a dry run.
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    pub added: usize,
+    /// already recorded, or repeated in the file
+    pub duplicate: usize,
+    /// not text, missing content or author, or the author opted out
+    pub invalid: usize,
+    /// added records with the Telegram user ID of their author
+    pub records: Vec<(i64, RecordModel)>,
+}
+
+/// numbers shown by `/stats`, of a group library or everything the viewer sees.
+pub struct RecordStats {
+    pub total: usize,
+    /// records quoting the viewer
+    pub mine: usize,
+    /// most used records, only the viewer's in private chats
+    pub top_records: Vec<RecordModel>,
+    /// username and count of the most quoted users
+    pub top_quoted: Vec<(String, i64)>,
+    /// username and count of who recorded the most
+    pub top_recorders: Vec<(String, i64)>,
 }
 
 impl Controller {
@@ -67,6 +208,163 @@ impl Controller {
         }
     }
 
+    /// update user search scope when `/scope` command called.
+    pub async fn set_user_search_all(&self, user_id: &i64, search_all: bool) -> Result<(), DbErr> {
+        let transaction = self.db.begin().await?;
+        if let Some(user) = self.get_user(user_id, &transaction).await? {
+            let mut user_active: UserActiveModel = user.into();
+            user_active.search_all_chats = Set(search_all);
+            user_active.save(&transaction).await?;
+        }
+        transaction.commit().await
+    }
+
+    pub async fn get_user_search_all(&self, user_id: &i64) -> Result<bool, DbErr> {
+        let transaction = self.db.begin().await?;
+        if let Some(user) = self.get_user(&user_id, &transaction).await? {
+            Ok(user.search_all_chats)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// remember the user is a member of a group chat.
+    pub async fn add_membership(
+        &self,
+        user_id: &i64,
+        username: &String,
+        chat_id: i64,
+    ) -> Result<(), DbErr> {
+        let transaction = self.db.begin().await?;
+        let user_id = match self.get_user(user_id, &transaction).await? {
+            Some(user) => user.id,
+            None => self
+                .setup_user(user_id, username, &transaction)
+                .await?
+                .id
+                .unwrap(),
+        };
+
+        if Membership::find()
+            .filter(MembershipColumn::UserId.eq(user_id))
+            .filter(MembershipColumn::ChatId.eq(chat_id))
+            .one(&transaction)
+            .await?
+            .is_none()
+        {
+            MembershipActiveModel {
+                user_id: Set(user_id),
+                chat_id: Set(chat_id),
+                ..Default::default()
+            }
+            .insert(&transaction)
+            .await?;
+        }
+        transaction.commit().await
+    }
+
+    /// forget the membership when the user left a group chat.
+    pub async fn remove_membership(&self, user_id: &i64, chat_id: i64) -> Result<(), DbErr> {
+        let transaction = self.db.begin().await?;
+        if let Some(user) = self.get_user(user_id, &transaction).await? {
+            Membership::delete_many()
+                .filter(MembershipColumn::UserId.eq(user.id))
+                .filter(MembershipColumn::ChatId.eq(chat_id))
+                .exec(&transaction)
+                .await?;
+        }
+        transaction.commit().await
+    }
+
+    /// libraries visible to `viewer` from chat `chat_id`.
+    ///
+    /// A group sees its own library and the global one. Private chats and inline
+    /// queries see the global library, or every library of the viewer's groups
+    /// when the viewer enabled it.
+    async fn scope_condition(
+        &self,
+        viewer: &i64,
+        chat_id: Option<i64>,
+        transaction: &DatabaseTransaction,
+    ) -> Result<Condition, DbErr> {
+        let global = Condition::any().add(RecordColumn::ChatId.is_null());
+
+        if let Some(chat_id) = chat_id {
+            return Ok(global.add(RecordColumn::ChatId.eq(chat_id)));
+        }
+
+        let user = match self.get_user(viewer, transaction).await? {
+            Some(user) if user.search_all_chats => user,
+            _ => return Ok(global),
+        };
+
+        let chats: Vec<i64> = Membership::find()
+            .filter(MembershipColumn::UserId.eq(user.id))
+            .all(transaction)
+            .await?
+            .into_iter()
+            .map(|membership| membership.chat_id)
+            .collect();
+
+        if chats.is_empty() {
+            Ok(global)
+        } else {
+            Ok(global.add(RecordColumn::ChatId.is_in(chats)))
+        }
+    }
+
+    /// records `viewer` may see from chat `chat_id`, the query has to join users.
+    async fn visibility_condition(
+        &self,
+        viewer: &i64,
+        chat_id: Option<i64>,
+        transaction: &DatabaseTransaction,
+    ) -> Result<Condition, DbErr> {
+        Ok(Condition::all()
+            .add(RecordColumn::DeletedAt.is_null())
+            .add(self.scope_condition(viewer, chat_id, transaction).await?)
+            // records of people who opted out are only visible to themselves
+            .add(
+                Condition::any()
+                    .add(UserColumn::OptOut.eq(false))
+                    .add(UserColumn::TgUid.eq(*viewer)),
+            )
+            // and so are records still waiting for approval
+            .add(
+                Condition::any()
+                    .add(RecordColumn::Approved.eq(true))
+                    .add(UserColumn::TgUid.eq(*viewer)),
+            ))
+    }
+
+    /// update user opt-out when `/optout` or `/optin` command called.
+    pub async fn set_user_opt_out(
+        &self,
+        user_id: &i64,
+        username: &String,
+        opt_out: bool,
+    ) -> Result<(), DbErr> {
+        let transaction = self.db.begin().await?;
+        let mut user_active = self.setup_user(user_id, username, &transaction).await?;
+        user_active.opt_out = Set(opt_out);
+        user_active.save(&transaction).await?;
+        transaction.commit().await
+    }
+
+    /// update user consent mode when `/consent` or `/noconsent` command called.
+    pub async fn set_user_consent(
+        &self,
+        user_id: &i64,
+        username: &String,
+        consent: bool,
+    ) -> Result<(), DbErr> {
+        let transaction = self.db.begin().await?;
+        let mut user_active = self.setup_user(user_id, username, &transaction).await?;
+        user_active.consent = Set(consent);
+        user_active.save(&transaction).await?;
+        transaction.commit().await
+    }
+
     async fn setup_user(
         &self,
         user_id: &i64,
@@ -92,6 +390,35 @@ impl Controller {
         }
     }
 
+    /// user of `user_id`, named `username` if it is known to be theirs.
+    ///
+    /// Without it the stored username is kept, and unknown users are created
+    /// without one.
+    async fn named_user(
+        &self,
+        user_id: &i64,
+        username: Option<&String>,
+        transaction: &DatabaseTransaction,
+    ) -> Result<UserActiveModel, DbErr> {
+        if let Some(username) = username {
+            return self.setup_user(user_id, username, transaction).await;
+        }
+
+        match self.get_user(user_id, transaction).await? {
+            Some(user) => Ok(user.into()),
+            None => {
+                UserActiveModel {
+                    tg_uid: Set(user_id.to_owned()),
+                    username: Set(None),
+                    notify: Set(true),
+                    ..Default::default()
+                }
+                .save(transaction)
+                .await
+            }
+        }
+    }
+
     async fn get_user(
         &self,
         user_id: &i64,
@@ -112,37 +439,689 @@ impl Controller {
     }
 
     /// get records when inline query called.
+    ///
+    /// Results are paged by `INLINE_RESULTS`, in a stable order so the client
+    /// can keep loading more.
     pub async fn get_records_by_keywords(
         &self,
-        key_word: &String,
+        query: &SearchQuery,
+        viewer: i64,
+        page: usize,
     ) -> Result<PaginatedRecordData, DbErr> {
-        let pagination = Record::find()
-            .find_also_related(User)
-            .filter(RecordColumn::Message.contains(key_word.as_str()))
-            .order_by_desc(RecordColumn::Hot)
-            .paginate(&self.db, 50); // 50 records seems ok.
+        let transaction = self.db.begin().await?;
+        let scope = self
+            .visibility_condition(&viewer, None, &transaction)
+            .await?;
+        let condition = Self::query_condition(query)
+            .add(RecordColumn::Approved.eq(true))
+            .add(scope);
+
+        let key_word = normalize(&query.search_text());
+        if key_word.trim().is_empty() {
+            return self
+                .get_hot_and_random_records(condition, viewer, page, &transaction)
+                .await;
+        }
+
+        let mut candidates = match fulltext_phrase(self.db.get_database_backend(), &key_word) {
+            Some(phrase) => {
+                self.fulltext_candidates(&phrase, condition.clone(), &transaction)
+                    .await?
+            }
+            // too short for the full-text index
+            None => {
+                self.substring_candidates(&key_word, condition.clone(), &transaction)
+                    .await?
+            }
+        };
+        if let Some(query) = pinyin_query(&key_word) {
+            merge_candidates(
+                &mut candidates,
+                self.pinyin_candidates(&query, condition.clone(), &transaction)
+                    .await?,
+            );
+        }
+        if candidates.is_empty() {
+            // no exact match, maybe a typo
+            candidates = self
+                .fuzzy_candidates(&key_word, condition, &transaction)
+                .await?;
+        }
+
+        let items_count = candidates.len();
+        let current_data = self
+            .rank_candidates(candidates, viewer, page, &transaction)
+            .await?;
         Ok(PaginatedRecordData {
-            items_count: pagination.num_items().await?,
-            pages_count: pagination.num_pages().await?,
-            current_data: pagination.fetch().await?,
+            items_count,
+            pages_count: items_count.div_ceil(INLINE_RESULTS),
+            current_data,
+            tags: HashMap::new(),
+            recorders: HashMap::new(),
         })
     }
 
+    /// records when there is nothing to match.
+    ///
+    /// The first page starts with records the viewer used lately, then mixes
+    /// trending records with random ones, the next pages continue with
+    /// trending records.
+    async fn get_hot_and_random_records(
+        &self,
+        condition: Condition,
+        viewer: i64,
+        page: usize,
+        transaction: &DatabaseTransaction,
+    ) -> Result<PaginatedRecordData, DbErr> {
+        let items_count = Record::find()
+            .inner_join(User)
+            .filter(condition.clone())
+            .count(transaction)
+            .await?;
+        let first_page = self
+            .first_hot_page(condition.clone(), viewer, transaction)
+            .await?;
+        let shown = first_page.len();
+
+        let current_data = if page == 0 {
+            first_page
+        } else {
+            // later pages leave out whatever the first one showed
+            let shown_ids: Vec<i64> = first_page.iter().map(|(record, _)| record.id).collect();
+            Self::hot_records(condition)
+                .find_also_related(User)
+                .filter(RecordColumn::Id.is_not_in(shown_ids))
+                .offset((page as u64 - 1) * INLINE_RESULTS as u64)
+                .limit(INLINE_RESULTS as u64)
+                .all(transaction)
+                .await?
+        };
+
+        let rest = items_count.saturating_sub(shown);
+        Ok(PaginatedRecordData {
+            items_count,
+            pages_count: 1 + rest.div_ceil(INLINE_RESULTS),
+            current_data,
+            tags: HashMap::new(),
+            recorders: HashMap::new(),
+        })
+    }
+
+    /// records matching `condition` by trend, callers join users.
+    fn hot_records(condition: Condition) -> Select<Record> {
+        Record::find()
+            .filter(condition)
+            .order_by_desc(RecordColumn::Trend)
+            .order_by_desc(RecordColumn::Hot)
+            .order_by_desc(RecordColumn::Id)
+    }
+
+    /// first page of an empty inline query: records `viewer` used lately, the
+    /// trending ones and random ones in between.
+    ///
+    /// Random records are picked the same way for a while, so later pages get
+    /// the same page back to leave it out.
+    async fn first_hot_page(
+        &self,
+        condition: Condition,
+        viewer: i64,
+        transaction: &DatabaseTransaction,
+    ) -> Result<Vec<(RecordModel, Option<UserModel>)>, DbErr> {
+        let seed = viewer as u64 ^ (now() / RANDOM_SEED_PERIOD) as u64;
+
+        let mut mixed = self
+            .used_records(viewer, condition.clone(), transaction)
+            .await?;
+        let mut shown_ids: Vec<i64> = mixed.iter().map(|(record, _)| record.id).collect();
+
+        let hottest: Vec<(RecordModel, Option<UserModel>)> = Self::hot_records(condition.clone())
+            .find_also_related(User)
+            .limit(HOT_RESULTS)
+            .all(transaction)
+            .await?
+            .into_iter()
+            .filter(|(record, _)| !shown_ids.contains(&record.id))
+            .collect();
+        shown_ids.extend(hottest.iter().map(|(record, _)| record.id));
+        // records used lately take the place of random ones to stay in one answer
+        let mut random = self
+            .random_records(
+                condition,
+                RANDOM_RESULTS.saturating_sub(mixed.len()),
+                seed,
+                transaction,
+            )
+            .await?
+            .into_iter()
+            .filter(move |(record, _)| !shown_ids.contains(&record.id));
+
+        // keep the trending on top, random records in between
+        for record in hottest {
+            mixed.push(record);
+            if let Some(record) = random.next() {
+                mixed.push(record);
+            }
+        }
+        mixed.extend(random);
+        Ok(mixed)
+    }
+
+    /// a random record visible from `chat_id` when `/random` command called,
+    /// only of `author` if given.
+    pub async fn get_random_record(
+        &self,
+        author: Option<i64>,
+        viewer: i64,
+        chat_id: Option<i64>,
+    ) -> Result<Option<(RecordModel, Option<UserModel>)>, DbErr> {
+        let transaction = self.db.begin().await?;
+        let scope = self
+            .visibility_condition(&viewer, chat_id, &transaction)
+            .await?;
+        let mut condition = Condition::all()
+            .add(RecordColumn::Approved.eq(true))
+            .add(scope);
+        if let Some(author) = author {
+            condition = condition.add(UserColumn::TgUid.eq(author));
+        }
+
+        Ok(self
+            .random_records(condition, 1, rand::random(), &transaction)
+            .await?
+            .pop())
+    }
+
+    /// records matching `condition` that `viewer` used the most lately.
+    async fn used_records(
+        &self,
+        viewer: i64,
+        condition: Condition,
+        transaction: &DatabaseTransaction,
+    ) -> Result<Vec<(RecordModel, Option<UserModel>)>, DbErr> {
+        let mut used: Vec<(i64, f64)> = self
+            .usage_scores(viewer, Condition::all(), USAGE_CANDIDATES, transaction)
+            .await?
+            .into_iter()
+            .collect();
+        used.sort_by(|(a_id, a), (b_id, b)| b.total_cmp(a).then(b_id.cmp(a_id)));
+        let ids: Vec<i64> = used
+            .into_iter()
+            .take(USAGE_RESULTS)
+            .map(|(id, _)| id)
+            .collect();
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut records = Record::find()
+            .find_also_related(User)
+            .filter(condition)
+            .filter(RecordColumn::Id.is_in(ids.clone()))
+            .all(transaction)
+            .await?;
+        records.sort_by_key(|(record, _)| ids.iter().position(|id| *id == record.id));
+        Ok(records)
+    }
+
+    /// how much `viewer` used records matching `condition` lately, by record id.
+    ///
+    /// Only the latest `limit` usages are loaded.
+    async fn usage_scores(
+        &self,
+        viewer: i64,
+        condition: Condition,
+        limit: u64,
+        transaction: &DatabaseTransaction,
+    ) -> Result<HashMap<i64, f64>, DbErr> {
+        let time = now();
+        Ok(RecordUsage::find()
+            .filter(RecordUsageColumn::TgUid.eq(viewer))
+            .filter(condition)
+            .order_by_desc(RecordUsageColumn::UsedAt)
+            .limit(limit)
+            .all(transaction)
+            .await?
+            .into_iter()
+            .map(|usage| {
+                (
+                    usage.record_id,
+                    usage_score(usage.count, usage.used_at, time),
+                )
+            })
+            .collect())
+    }
+
+    /// records used the most lately, all-time hot breaks ties.
+    pub async fn get_trending_records(
+        &self,
+        viewer: i64,
+        chat_id: Option<i64>,
+    ) -> Result<Vec<(RecordModel, Option<UserModel>)>, DbErr> {
+        let transaction = self.db.begin().await?;
+        let scope = self
+            .visibility_condition(&viewer, chat_id, &transaction)
+            .await?;
+
+        Record::find()
+            .find_also_related(User)
+            .filter(RecordColumn::Approved.eq(true))
+            .filter(RecordColumn::Trend.gt(0.0))
+            .filter(scope)
+            .order_by_desc(RecordColumn::Trend)
+            .order_by_desc(RecordColumn::Hot)
+            .limit(TRENDING_RESULTS)
+            .all(&transaction)
+            .await
+    }
+
+    /// up to `count` random records matching `condition`, the query has to
+    /// join users.
+    ///
+    /// Records of random ids are loaded at once, so no query sorts the whole
+    /// table. If none of them matches, the first record from a random id is
+    /// taken, records after gaps in ids are a bit more likely then.
+    async fn random_records(
+        &self,
+        condition: Condition,
+        count: usize,
+        seed: u64,
+        transaction: &DatabaseTransaction,
+    ) -> Result<Vec<(RecordModel, Option<UserModel>)>, DbErr> {
+        if count == 0 {
+            return Ok(vec![]);
+        }
+
+        let (min, max) = match Record::find()
+            .select_only()
+            .column_as(Expr::tbl(Record, RecordColumn::Id).min(), "min")
+            .column_as(Expr::tbl(Record, RecordColumn::Id).max(), "max")
+            .inner_join(User)
+            .filter(condition.clone())
+            .into_model::<IdRange>()
+            .one(transaction)
+            .await?
+        {
+            Some(IdRange {
+                min: Some(min),
+                max: Some(max),
+            }) => (min, max),
+            _ => return Ok(vec![]),
+        };
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let picks: Vec<i64> = (0..count * RANDOM_PICKS)
+            .map(|_| rng.gen_range(min..=max))
+            .collect();
+
+        let mut records = Record::find()
+            .find_also_related(User)
+            .filter(condition.clone())
+            .filter(RecordColumn::Id.is_in(picks.clone()))
+            .all(transaction)
+            .await?;
+        if records.is_empty() {
+            // few records match, e.g. of one author
+            return Ok(Record::find()
+                .find_also_related(User)
+                .filter(condition)
+                .filter(RecordColumn::Id.gte(picks[0]))
+                .order_by_asc(RecordColumn::Id)
+                .one(transaction)
+                .await?
+                .into_iter()
+                .collect());
+        }
+
+        records.sort_by_key(|(record, _)| picks.iter().position(|id| *id == record.id));
+        records.truncate(count);
+        Ok(records)
+    }
+
+    /// filters of an inline query, the query has to join users.
+    fn query_condition(query: &SearchQuery) -> Condition {
+        let mut condition = Condition::all().add(Self::tags_condition(&query.tags));
+
+        if let Some(from) = &query.from {
+            condition = condition.add(UserColumn::Username.eq(from.as_str()));
+        }
+        if let Some(after) = query.after {
+            condition = condition.add(RecordColumn::CreatedAt.gte(after));
+        }
+        if let Some(hot) = query.hot {
+            condition = condition.add(match hot {
+                HotFilter::Greater(hot) => RecordColumn::Hot.gt(hot),
+                HotFilter::GreaterOrEqual(hot) => RecordColumn::Hot.gte(hot),
+                HotFilter::Less(hot) => RecordColumn::Hot.lt(hot),
+                HotFilter::LessOrEqual(hot) => RecordColumn::Hot.lte(hot),
+                HotFilter::Equal(hot) => RecordColumn::Hot.eq(hot),
+            });
+        }
+        for phrase in &query.phrases {
+            condition = condition.add(RecordColumn::Normalized.contains(&normalize(phrase)));
+        }
+        for exclude in &query.excludes {
+            condition = condition
+                .add(RecordColumn::Normalized.not_like(&format!("%{}%", normalize(exclude))));
+        }
+
+        condition
+    }
+
+    /// ids of the records matching `condition`, the query has users joined.
+    fn candidate_select(condition: Condition) -> Select<Record> {
+        Record::find()
+            .select_only()
+            .column(RecordColumn::Id)
+            .inner_join(User)
+            .filter(condition)
+    }
+
+    /// records matching `phrase` in the full-text index by relevance.
+    async fn fulltext_candidates(
+        &self,
+        phrase: &str,
+        condition: Condition,
+        transaction: &DatabaseTransaction,
+    ) -> Result<HashMap<i64, f64>, DbErr> {
+        let mut select = Self::candidate_select(condition);
+
+        select = match self.db.get_database_backend() {
+            DbBackend::Sqlite => {
+                QuerySelect::query(&mut select).join(
+                    JoinType::InnerJoin,
+                    Alias::new("records_fts"),
+                    Expr::cust("records_fts.rowid = records.id"),
+                );
+                select
+                    .column_as(Expr::cust("-bm25(records_fts)"), "relevance")
+                    .filter(Expr::cust_with_values("records_fts MATCH ?", vec![phrase]))
+            }
+            DbBackend::Postgres => select
+                .column_as(
+                    Expr::cust_with_values(
+                        "ts_rank(records.normalized_tsv, phraseto_tsquery('simple', ?))::float8",
+                        vec![phrase],
+                    ),
+                    "relevance",
+                )
+                .filter(Expr::cust_with_values(
+                    "records.normalized_tsv @@ phraseto_tsquery('simple', ?)",
+                    vec![phrase],
+                )),
+            DbBackend::MySql => select
+                .column_as(
+                    Expr::cust_with_values(
+                        "MATCH (records.normalized) AGAINST (? IN BOOLEAN MODE)",
+                        vec![phrase],
+                    ),
+                    "relevance",
+                )
+                .filter(Expr::cust_with_values(
+                    "MATCH (records.normalized) AGAINST (? IN BOOLEAN MODE)",
+                    vec![phrase],
+                )),
+        };
+
+        let ranked = select
+            .order_by_desc(Expr::cust("relevance"))
+            .limit(SEARCH_CANDIDATES)
+            .into_model::<RankedRecord>()
+            .all(transaction)
+            .await?;
+
+        // scale to 0..=1, backends rank on different scales
+        let max_relevance = ranked
+            .iter()
+            .map(|record| record.relevance)
+            .fold(f64::EPSILON, f64::max);
+        Ok(ranked
+            .into_iter()
+            .map(|record| (record.id, record.relevance / max_relevance))
+            .collect())
+    }
+
+    /// records containing `key_word`, all equally relevant.
+    async fn substring_candidates(
+        &self,
+        key_word: &str,
+        condition: Condition,
+        transaction: &DatabaseTransaction,
+    ) -> Result<HashMap<i64, f64>, DbErr> {
+        Ok(Self::candidate_select(condition)
+            .filter(RecordColumn::Normalized.contains(key_word))
+            .order_by_desc(RecordColumn::Trend)
+            .limit(SEARCH_CANDIDATES)
+            .into_model::<RecordId>()
+            .all(transaction)
+            .await?
+            .into_iter()
+            .map(|record| (record.id, 1.0))
+            .collect())
+    }
+
+    /// records whose pinyin or pinyin initials contain `query`.
+    async fn pinyin_candidates(
+        &self,
+        query: &str,
+        condition: Condition,
+        transaction: &DatabaseTransaction,
+    ) -> Result<HashMap<i64, f64>, DbErr> {
+        Ok(Self::candidate_select(condition)
+            .column(RecordColumn::Pinyin)
+            .filter(
+                Condition::any()
+                    .add(RecordColumn::Pinyin.contains(query))
+                    .add(RecordColumn::Initials.contains(query)),
+            )
+            .order_by_desc(RecordColumn::Trend)
+            .limit(SEARCH_CANDIDATES)
+            .into_model::<SpelledRecord>()
+            .all(transaction)
+            .await?
+            .into_iter()
+            .map(|record| {
+                let relevance = match record.pinyin {
+                    Some(pinyin) if pinyin.contains(query) => PINYIN_RELEVANCE,
+                    _ => INITIALS_RELEVANCE,
+                };
+                (record.id, relevance)
+            })
+            .collect())
+    }
+
+    /// records similar to `key_word` by trigrams.
+    async fn fuzzy_candidates(
+        &self,
+        key_word: &str,
+        condition: Condition,
+        transaction: &DatabaseTransaction,
+    ) -> Result<HashMap<i64, f64>, DbErr> {
+        if self.db.get_database_backend() == DbBackend::Postgres {
+            // `<%` matches by this threshold and can use the trigram index
+            transaction
+                .execute(Statement::from_string(
+                    DbBackend::Postgres,
+                    format!(
+                        "SET LOCAL pg_trgm.word_similarity_threshold = {}",
+                        FUZZY_THRESHOLD
+                    ),
+                ))
+                .await?;
+
+            return Ok(Self::candidate_select(condition)
+                .column_as(
+                    Expr::cust_with_values(
+                        "word_similarity(?, records.normalized)::float8",
+                        vec![key_word],
+                    ),
+                    "relevance",
+                )
+                .filter(Expr::cust_with_values(
+                    "? <% records.normalized",
+                    vec![key_word],
+                ))
+                .order_by_desc(Expr::cust("relevance"))
+                .limit(SEARCH_CANDIDATES)
+                .into_model::<RankedRecord>()
+                .all(transaction)
+                .await?
+                .into_iter()
+                .map(|record| (record.id, record.relevance))
+                .collect());
+        }
+
+        Ok(Self::candidate_select(condition)
+            .column(RecordColumn::Normalized)
+            .order_by_desc(RecordColumn::Trend)
+            .limit(FUZZY_CANDIDATES)
+            .into_model::<NormalizedRecord>()
+            .all(transaction)
+            .await?
+            .into_iter()
+            .filter_map(|record| {
+                let similarity = trigram_similarity(key_word, &record.normalized?);
+                if similarity >= FUZZY_THRESHOLD {
+                    Some((record.id, similarity))
+                } else {
+                    None
+                }
+            })
+            .collect())
+    }
+
+    /// load candidate records, ranked by relevance combined with trend and
+    /// boosted by the usage of `viewer`.
+    async fn rank_candidates(
+        &self,
+        candidates: HashMap<i64, f64>,
+        viewer: i64,
+        page: usize,
+        transaction: &DatabaseTransaction,
+    ) -> Result<Vec<(RecordModel, Option<UserModel>)>, DbErr> {
+        if candidates.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let candidate_ids: Vec<i64> = candidates.keys().copied().collect();
+        let hots = Record::find()
+            .select_only()
+            .column(RecordColumn::Id)
+            .column(RecordColumn::Hot)
+            .column(RecordColumn::Trend)
+            .filter(RecordColumn::Id.is_in(candidate_ids.clone()))
+            .into_model::<RecordHot>()
+            .all(transaction)
+            .await?;
+        let usages = self
+            .usage_scores(
+                viewer,
+                Condition::all().add(RecordUsageColumn::RecordId.is_in(candidate_ids.clone())),
+                candidate_ids.len() as u64,
+                transaction,
+            )
+            .await?;
+
+        // trend is scaled to 0..=1 among the candidates like relevance
+        let time = now();
+        let max_trend = hots
+            .iter()
+            .map(|record| trend_score(record.trend, time))
+            .fold(f64::EPSILON, f64::max);
+        let max_usage = usages.values().copied().fold(f64::EPSILON, f64::max);
+        let score = |record: &RecordHot| {
+            (1.0 - HOT_WEIGHT) * candidates[&record.id]
+                + HOT_WEIGHT * trend_score(record.trend, time) / max_trend
+                + USAGE_WEIGHT * usages.get(&record.id).copied().unwrap_or_default() / max_usage
+        };
+        let mut ranked: Vec<(f64, i64, i64)> = hots
+            .iter()
+            .map(|record| (score(record), record.hot, record.id))
+            .collect();
+        // all-time hot breaks ties, then newer records first so pages don't shift
+        ranked.sort_by(|(a, a_hot, a_id), (b, b_hot, b_id)| {
+            b.total_cmp(a).then(b_hot.cmp(a_hot)).then(b_id.cmp(a_id))
+        });
+
+        let ids: Vec<i64> = ranked
+            .into_iter()
+            .skip(page * INLINE_RESULTS)
+            .take(INLINE_RESULTS)
+            .map(|(_, _, id)| id)
+            .collect();
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut records = Record::find()
+            .find_also_related(User)
+            .filter(RecordColumn::Id.is_in(ids.clone()))
+            .all(transaction)
+            .await?;
+        records.sort_by_key(|(record, _)| ids.iter().position(|id| *id == record.id));
+        Ok(records)
+    }
+
+    /// fill in search columns of records saved before they existed, run on startup.
+    pub async fn backfill_search_text(&self) -> Result<(), DbErr> {
+        loop {
+            let transaction = self.db.begin().await?;
+            let records = Record::find()
+                .filter(
+                    Condition::any()
+                        .add(RecordColumn::Pinyin.is_null())
+                        .add(RecordColumn::Normalized.is_null()),
+                )
+                .limit(BACKFILL_BATCH)
+                .all(&transaction)
+                .await?;
+            if records.is_empty() {
+                return Ok(());
+            }
+
+            log_info_ln!("computing search text of {} records", records.len());
+            for record in records {
+                let normalized = normalize(&record.message);
+                let mut record_active: RecordActiveModel = record.into();
+                record_active.pinyin = Set(Some(pinyin(&normalized)));
+                record_active.initials = Set(Some(initials(&normalized)));
+                record_active.normalized = Set(Some(normalized));
+                record_active.save(&transaction).await?;
+            }
+            transaction.commit().await?;
+        }
+    }
+
     /// get records when `/list` command called or inline button request.
     pub async fn get_records_by_userid_with_pagination(
         &self,
         user_id: i64,
         page: usize,
+        tags: &[String],
+        keywords: &str,
+        viewer: i64,
+        chat_id: Option<i64>,
     ) -> Result<Option<PaginatedRecordData>, DbErr> {
         let transaction = self.db.begin().await?;
         if let Some(user) = self.get_user(&user_id, &transaction).await? {
+            let scope = self
+                .visibility_condition(&viewer, chat_id, &transaction)
+                .await?;
             let pagination = Record::find()
                 .find_also_related(User)
                 .filter(RecordColumn::UserId.eq(user.id))
+                .filter(Self::tags_condition(tags))
+                .filter(Self::keywords_condition(keywords))
+                .filter(scope)
                 .order_by_asc(RecordColumn::Id)
                 .paginate(&transaction, PAGE_SIZE);
+            let current_data = pagination.fetch_page(page).await?;
             Ok(Some(PaginatedRecordData {
-                current_data: pagination.fetch_page(page).await?,
+                tags: self.load_tags(&current_data, &transaction).await?,
+                recorders: match user.tg_uid == viewer {
+                    true => self.load_recorders(&current_data, &transaction).await?,
+                    false => HashMap::new(),
+                },
+                current_data,
                 items_count: pagination.num_items().await?,
                 pages_count: pagination.num_pages().await?,
             }))
@@ -152,49 +1131,830 @@ impl Controller {
         }
     }
 
+    /// get records recorded by `recorder` when `/recorded` command called or
+    /// inline button request.
+    pub async fn get_records_by_recorder_with_pagination(
+        &self,
+        recorder: i64,
+        page: usize,
+        viewer: i64,
+        chat_id: Option<i64>,
+    ) -> Result<Option<PaginatedRecordData>, DbErr> {
+        let transaction = self.db.begin().await?;
+        if let Some(user) = self.get_user(&recorder, &transaction).await? {
+            let scope = self
+                .visibility_condition(&viewer, chat_id, &transaction)
+                .await?;
+            let pagination = Record::find()
+                .find_also_related(User)
+                .filter(RecordColumn::RecordedBy.eq(user.id))
+                .filter(scope)
+                .order_by_asc(RecordColumn::Id)
+                .paginate(&transaction, PAGE_SIZE);
+            let current_data = pagination.fetch_page(page).await?;
+            Ok(Some(PaginatedRecordData {
+                tags: self.load_tags(&current_data, &transaction).await?,
+                recorders: HashMap::new(),
+                current_data,
+                items_count: pagination.num_items().await?,
+                pages_count: pagination.num_pages().await?,
+            }))
+        } else {
+            log_error_ln!("cannot find user tg_uid={}", recorder);
+            Ok(None)
+        }
+    }
+
+    /// get all records of `user_id` when `/export` command called, with their
+    /// tags.
+    ///
+    /// Users always get their own records, records of others are limited to
+    /// what `viewer` may see.
+    pub async fn get_records_for_export(
+        &self,
+        user_id: i64,
+        viewer: i64,
+        chat_id: Option<i64>,
+    ) -> Result<
+        Option<(
+            Vec<(RecordModel, Option<UserModel>)>,
+            HashMap<i64, Vec<String>>,
+        )>,
+        DbErr,
+    > {
+        let transaction = self.db.begin().await?;
+        let user = match self.get_user(&user_id, &transaction).await? {
+            Some(user) => user,
+            None => return Ok(None),
+        };
+        let condition = match user_id == viewer {
+            true => Condition::all().add(RecordColumn::DeletedAt.is_null()),
+            false => {
+                self.visibility_condition(&viewer, chat_id, &transaction)
+                    .await?
+            }
+        };
+
+        let records = Record::find()
+            .find_also_related(User)
+            .filter(RecordColumn::UserId.eq(user.id))
+            .filter(condition)
+            .order_by_asc(RecordColumn::Id)
+            .all(&transaction)
+            .await?;
+        let tags = self.load_tags(&records, &transaction).await?;
+        Ok(Some((records, tags)))
+    }
+
+    /// get statistics when `/stats` command called, of the group library in
+    /// groups and of the viewer in private chats.
+    pub async fn get_stats(&self, viewer: i64, chat_id: Option<i64>) -> Result<RecordStats, DbErr> {
+        let transaction = self.db.begin().await?;
+        let scope = match chat_id {
+            // only the group's own library, not the global one it also sees
+            Some(chat_id) => Condition::all()
+                .add(RecordColumn::ChatId.eq(chat_id))
+                .add(RecordColumn::DeletedAt.is_null())
+                .add(RecordColumn::Approved.eq(true))
+                .add(UserColumn::OptOut.eq(false)),
+            None => {
+                self.visibility_condition(&viewer, None, &transaction)
+                    .await?
+            }
+        };
+        let mine = Condition::all()
+            .add(scope.clone())
+            .add(UserColumn::TgUid.eq(viewer));
+
+        let total = Record::find()
+            .inner_join(User)
+            .filter(scope.clone())
+            .count(&transaction)
+            .await?;
+        let mine_count = Record::find()
+            .inner_join(User)
+            .filter(mine.clone())
+            .count(&transaction)
+            .await?;
+
+        let top_records = Record::find()
+            .inner_join(User)
+            .filter(match chat_id {
+                Some(_) => scope.clone(),
+                None => mine,
+            })
+            .filter(RecordColumn::Hot.gt(0))
+            .order_by_desc(RecordColumn::Hot)
+            .order_by_desc(RecordColumn::Id)
+            .limit(STATS_TOP)
+            .all(&transaction)
+            .await?;
+
+        Ok(RecordStats {
+            total,
+            mine: mine_count,
+            top_records,
+            top_quoted: self
+                .top_users(RecordColumn::UserId, scope.clone(), &transaction)
+                .await?,
+            top_recorders: self
+                .top_users(RecordColumn::RecordedBy, scope, &transaction)
+                .await?,
+        })
+    }
+
+    /// users with the most records matching `condition` by `column`, which
+    /// refers to users, with the counts.
+    async fn top_users(
+        &self,
+        column: RecordColumn,
+        condition: Condition,
+        transaction: &DatabaseTransaction,
+    ) -> Result<Vec<(String, i64)>, DbErr> {
+        let counts = Record::find()
+            .select_only()
+            .column_as(column, "user_id")
+            .column_as(Expr::tbl(Record, RecordColumn::Id).count(), "records")
+            .inner_join(User)
+            .filter(condition)
+            .filter(column.is_not_null())
+            .group_by(column)
+            .order_by_desc(Expr::cust("records"))
+            .limit(STATS_TOP)
+            .into_model::<UserRecords>()
+            .all(transaction)
+            .await?;
+        if counts.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let users: HashMap<i64, UserModel> = User::find()
+            .filter(UserColumn::Id.is_in(counts.iter().map(|count| count.user_id)))
+            .all(transaction)
+            .await?
+            .into_iter()
+            .map(|user| (user.id, user))
+            .collect();
+
+        Ok(counts
+            .into_iter()
+            .filter_map(|count| {
+                let user = users.get(&count.user_id)?;
+                let name = match &user.username {
+                    Some(username) => username.to_owned(),
+                    None => user.tg_uid.to_string(),
+                };
+                Some((name, count.records))
+            })
+            .collect())
+    }
+
     /// add record forward a message to bot.
+    ///
+    /// The record is pending if the quoted user is in consent mode and someone
+    /// else (`recorder_id`) forwarded it.
     pub async fn add_record(
         &self,
         user_id: i64,
-        username: &String,
-        text: String,
-    ) -> Result<(), DbErr> {
+        username: Option<&String>,
+        recorder_id: i64,
+        recorder_name: Option<&String>,
+        chat_id: Option<i64>,
+        content: RecordContent,
+    ) -> Result<AddRecordResult, DbErr> {
         let transaction = self.db.begin().await?;
-        let user = self.setup_user(&user_id, &username, &transaction).await?;
-        RecordActiveModel {
-            message: Set(text),
-            user_id: user.id,
-            ..Default::default()
+        let mut approved = true;
+        let mut trashed = None;
+        if let Some(user) = self.get_user(&user_id, &transaction).await? {
+            if user.opt_out {
+                return Ok(AddRecordResult::OptedOut);
+            }
+            approved = !user.consent || recorder_id == user_id;
+
+            // every library has its own copy
+            if let Some(record) = Record::find()
+                .filter(RecordColumn::UserId.eq(user.id))
+                .filter(RecordColumn::Message.eq(content.text.as_str()))
+                .filter(RecordColumn::FileUniqueId.eq(content.file_unique_id.as_str()))
+                .filter(match chat_id {
+                    Some(chat_id) => RecordColumn::ChatId.eq(chat_id),
+                    None => RecordColumn::ChatId.is_null(),
+                })
+                .one(&transaction)
+                .await?
+            {
+                if record.deleted_at.is_none() {
+                    return Ok(AddRecordResult::Duplicate(record));
+                }
+
+                // the author recording a trashed record again brings it back
+                if recorder_id == user_id {
+                    let mut record_active: RecordActiveModel = record.into();
+                    record_active.deleted_at = Set(None);
+                    let record = record_active.update(&transaction).await?;
+                    transaction.commit().await?;
+                    return Ok(AddRecordResult::Added(record));
+                }
+                trashed = Some(record);
+            }
         }
-        .insert(&transaction)
-        .await?;
-        transaction.commit().await
+
+        let user = self.named_user(&user_id, username, &transaction).await?;
+        let recorded_by = if recorder_id == user_id {
+            user.id.clone().unwrap()
+        } else {
+            self.named_user(&recorder_id, recorder_name, &transaction)
+                .await?
+                .id
+                .unwrap()
+        };
+        let normalized = normalize(&content.text);
+        let record_pinyin = pinyin(&normalized);
+        let record_initials = initials(&normalized);
+        let record = match trashed {
+            // someone else records it anew, the author's consent applies again
+            Some(record) => {
+                let mut record_active: RecordActiveModel = record.into();
+                record_active.deleted_at = Set(None);
+                record_active.chat_id = Set(chat_id);
+                record_active.entities = Set(content.entities);
+                record_active.file_id = Set(content.file_id);
+                record_active.created_at = Set(content.created_at.or(Some(now())));
+                record_active.approved = Set(approved);
+                record_active.recorded_by = Set(Some(recorded_by));
+                record_active.update(&transaction).await?
+            }
+            None => {
+                RecordActiveModel {
+                    chat_id: Set(chat_id),
+                    message: Set(content.text),
+                    entities: Set(content.entities),
+                    kind: Set(content.kind),
+                    file_id: Set(content.file_id),
+                    file_unique_id: Set(content.file_unique_id),
+                    pinyin: Set(Some(record_pinyin)),
+                    initials: Set(Some(record_initials)),
+                    normalized: Set(Some(normalized)),
+                    created_at: Set(content.created_at.or(Some(now()))),
+                    approved: Set(approved),
+                    recorded_by: Set(Some(recorded_by)),
+                    user_id: user.id,
+                    ..Default::default()
+                }
+                .insert(&transaction)
+                .await?
+            }
+        };
+        transaction.commit().await?;
+        Ok(AddRecordResult::Added(record))
     }
 
-    /// del record when `/delete` command called.
-    pub async fn del_record(&self, id: i64, user_id: i64) -> Result<(), DbErr> {
+    /// save records of an `/import` file to the global library as recorded by
+    /// `recorder_id`, all in one transaction.
+    ///
+    /// A dry run rolls the transaction back, so the summary tells what the
+    /// import would do.
+    pub async fn import_records(
+        &self,
+        recorder_id: i64,
+        recorder_name: &String,
+        records: Vec<ExportRecord>,
+        dry_run: bool,
+    ) -> Result<ImportSummary, DbErr> {
         let transaction = self.db.begin().await?;
+        let recorder = self
+            .setup_user(&recorder_id, recorder_name, &transaction)
+            .await?
+            .id
+            .unwrap();
+        let mut summary = ImportSummary::default();
 
-        if let Some(user) = self.get_user(&user_id, &transaction).await? {
-            RecordActiveModel {
-                id: Set(id),
+        for record in records {
+            // file IDs only work for the bot that gave them, so media of other
+            // bots would break inline answers
+            if record.media_kind() != MediaKind::Text || record.message.trim().is_empty() {
+                summary.invalid += 1;
+                continue;
+            }
+            let user = match self.import_user(&record, &transaction).await? {
+                Some(user) if !user.opt_out => user,
+                _ => {
+                    summary.invalid += 1;
+                    continue;
+                }
+            };
+
+            if Record::find()
+                .filter(RecordColumn::UserId.eq(user.id))
+                .filter(RecordColumn::Message.eq(record.message.as_str()))
+                .filter(RecordColumn::FileUniqueId.eq(""))
+                .filter(RecordColumn::ChatId.is_null())
+                .one(&transaction)
+                .await?
+                .is_some()
+            {
+                summary.duplicate += 1;
+                continue;
+            }
+
+            let tags = record.tag_names();
+            let entities = record.checked_entities();
+            // the file can say anything, only the own records keep their hot
+            let hot = match user.tg_uid == recorder_id {
+                true => record.hot.max(0),
+                false => 0,
+            };
+            let normalized = normalize(&record.message);
+            let record_pinyin = pinyin(&normalized);
+            let record_initials = initials(&normalized);
+            let added = RecordActiveModel {
+                message: Set(record.message),
+                entities: Set(entities),
+                kind: Set(MediaKind::Text),
+                file_unique_id: Set(String::new()),
+                hot: Set(hot),
+                pinyin: Set(Some(record_pinyin)),
+                initials: Set(Some(record_initials)),
+                normalized: Set(Some(normalized)),
+                created_at: Set(record.created_at.or(Some(now()))),
+                approved: Set(!user.consent || user.tg_uid == recorder_id),
+                recorded_by: Set(Some(recorder)),
                 user_id: Set(user.id),
                 ..Default::default()
             }
-            .delete(&transaction)
+            .insert(&transaction)
             .await?;
+            self.tag_record(added.id, &tags, &transaction).await?;
+            summary.added += 1;
+            summary.records.push((user.tg_uid, added));
         }
-        transaction.commit().await
+
+        if dry_run {
+            transaction.rollback().await?;
+        } else {
+            transaction.commit().await?;
+        }
+        Ok(summary)
+    }
+
+    /// the quoted user of an imported record, by Telegram user ID or else by
+    /// username.
+    ///
+    /// Users are created if the file has their ID, without the username of the
+    /// file which may be forged, it is set once they use the bot.
+    async fn import_user(
+        &self,
+        record: &ExportRecord,
+        transaction: &DatabaseTransaction,
+    ) -> Result<Option<UserModel>, DbErr> {
+        let username = match record.author.trim() {
+            "" => None,
+            author if author.starts_with("@") => Some(author.to_string()),
+            author => Some(format!("@{}", author)),
+        };
+
+        let author_id = match record.author_id {
+            Some(author_id) => author_id,
+            None => {
+                return match username {
+                    Some(username) => {
+                        User::find()
+                            .filter(UserColumn::Username.eq(username))
+                            .one(transaction)
+                            .await
+                    }
+                    None => Ok(None),
+                }
+            }
+        };
+
+        match self.get_user(&author_id, transaction).await? {
+            Some(user) => Ok(Some(user)),
+            None => UserActiveModel {
+                tg_uid: Set(author_id),
+                username: Set(None),
+                notify: Set(true),
+                ..Default::default()
+            }
+            .insert(transaction)
+            .await
+            .map(Some),
+        }
+    }
+
+    /// approve or reject a pending record of `user_id`, rejected ones are deleted.
+    ///
+    /// Returns `false` if there is no such pending record.
+    pub async fn review_record(&self, id: i64, user_id: i64, approve: bool) -> Result<bool, DbErr> {
+        let transaction = self.db.begin().await?;
+        let user = match self.get_user(&user_id, &transaction).await? {
+            Some(user) => user,
+            None => return Ok(false),
+        };
+
+        let record = match Record::find_by_id(id)
+            .filter(RecordColumn::UserId.eq(user.id))
+            .filter(RecordColumn::Approved.eq(false))
+            .filter(RecordColumn::DeletedAt.is_null())
+            .one(&transaction)
+            .await?
+        {
+            Some(record) => record,
+            None => return Ok(false),
+        };
+
+        if approve {
+            let mut record_active: RecordActiveModel = record.into();
+            record_active.approved = Set(true);
+            record_active.save(&transaction).await?;
+        } else {
+            RecordTag::delete_many()
+                .filter(RecordTagColumn::RecordId.eq(id))
+                .exec(&transaction)
+                .await?;
+            RecordUsage::delete_many()
+                .filter(RecordUsageColumn::RecordId.eq(id))
+                .exec(&transaction)
+                .await?;
+            Record::delete_many()
+                .filter(RecordColumn::Id.eq(id))
+                .exec(&transaction)
+                .await?;
+        }
+
+        transaction.commit().await?;
+        Ok(true)
+    }
+
+    /// tag a record visible from `chat_id` when `/tag` command called.
+    ///
+    /// Returns `false` if there is no such record.
+    pub async fn add_tags(
+        &self,
+        id: i64,
+        viewer: i64,
+        chat_id: Option<i64>,
+        tags: &[String],
+    ) -> Result<bool, DbErr> {
+        let transaction = self.db.begin().await?;
+        let scope = self
+            .visibility_condition(&viewer, chat_id, &transaction)
+            .await?;
+        if Record::find_by_id(id)
+            .find_also_related(User)
+            .filter(scope)
+            .one(&transaction)
+            .await?
+            .is_none()
+        {
+            return Ok(false);
+        }
+
+        self.tag_record(id, tags, &transaction).await?;
+
+        transaction.commit().await?;
+        Ok(true)
+    }
+
+    /// attach tags named `tags` to the record, creating missing tags.
+    async fn tag_record(
+        &self,
+        id: i64,
+        tags: &[String],
+        transaction: &DatabaseTransaction,
+    ) -> Result<(), DbErr> {
+        for name in tags {
+            let tag_id = match Tag::find()
+                .filter(TagColumn::Name.eq(name.as_str()))
+                .one(transaction)
+                .await?
+            {
+                Some(tag) => tag.id,
+                None => {
+                    TagActiveModel {
+                        name: Set(name.to_owned()),
+                        ..Default::default()
+                    }
+                    .insert(transaction)
+                    .await?
+                    .id
+                }
+            };
+
+            if RecordTag::find_by_id((id, tag_id))
+                .one(transaction)
+                .await?
+                .is_none()
+            {
+                RecordTagActiveModel {
+                    record_id: Set(id),
+                    tag_id: Set(tag_id),
+                }
+                .insert(transaction)
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// records tagged with every tag in `tags`.
+    fn tags_condition(tags: &[String]) -> Condition {
+        tags.iter().fold(Condition::all(), |condition, tag| {
+            condition.add(
+                RecordColumn::Id.in_subquery(
+                    Query::select()
+                        .column(RecordTagColumn::RecordId)
+                        .from(RecordTag)
+                        .inner_join(
+                            Tag,
+                            Expr::tbl(Tag, TagColumn::Id).equals(RecordTag, RecordTagColumn::TagId),
+                        )
+                        .and_where(Expr::tbl(Tag, TagColumn::Name).eq(tag.as_str()))
+                        .to_owned(),
+                ),
+            )
+        })
+    }
+
+    /// records containing every word of `keywords`, in text or in pinyin.
+    fn keywords_condition(keywords: &str) -> Condition {
+        normalize(keywords)
+            .split_whitespace()
+            .fold(Condition::all(), |condition, word| {
+                let mut matches = Condition::any().add(RecordColumn::Normalized.contains(word));
+                if let Some(query) = pinyin_query(word) {
+                    matches = matches
+                        .add(RecordColumn::Pinyin.contains(&query))
+                        .add(RecordColumn::Initials.contains(&query));
+                }
+                condition.add(matches)
+            })
+    }
+
+    async fn load_tags(
+        &self,
+        records: &[(RecordModel, Option<UserModel>)],
+        transaction: &DatabaseTransaction,
+    ) -> Result<HashMap<i64, Vec<String>>, DbErr> {
+        let mut tags: HashMap<i64, Vec<String>> = HashMap::new();
+        let ids: Vec<i64> = records.iter().map(|(record, _)| record.id).collect();
+        if ids.is_empty() {
+            return Ok(tags);
+        }
+
+        for (record_tag, tag) in RecordTag::find()
+            .find_also_related(Tag)
+            .filter(RecordTagColumn::RecordId.is_in(ids))
+            .order_by_asc(RecordTagColumn::TagId)
+            .all(transaction)
+            .await?
+        {
+            if let Some(tag) = tag {
+                tags.entry(record_tag.record_id).or_default().push(tag.name);
+            }
+        }
+        Ok(tags)
+    }
+
+    /// names of who recorded the records, records people recorded themselves
+    /// are skipped.
+    async fn load_recorders(
+        &self,
+        records: &[(RecordModel, Option<UserModel>)],
+        transaction: &DatabaseTransaction,
+    ) -> Result<HashMap<i64, String>, DbErr> {
+        let mut recorders = HashMap::new();
+        let ids: Vec<i64> = records
+            .iter()
+            .filter_map(|(record, _)| record.recorded_by)
+            .collect();
+        if ids.is_empty() {
+            return Ok(recorders);
+        }
+
+        let users: HashMap<i64, String> = User::find()
+            .filter(UserColumn::Id.is_in(ids))
+            .all(transaction)
+            .await?
+            .into_iter()
+            .map(|user| (user.id, user.username.unwrap_or_default()))
+            .collect();
+
+        for (record, _) in records {
+            match record.recorded_by {
+                Some(recorded_by) if recorded_by != record.user_id => {
+                    if let Some(name) = users.get(&recorded_by) {
+                        recorders.insert(record.id, name.to_owned());
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(recorders)
+    }
+
+    /// move record to trash when `/del` command called.
+    ///
+    /// Returns `false` if `user_id` has no such record.
+    pub async fn del_record(&self, id: i64, user_id: i64) -> Result<bool, DbErr> {
+        self.set_record_deleted_at(id, user_id, Some(now())).await
+    }
+
+    /// move records in `ids` of `user_id` to trash when `/del` command called
+    /// with several ids.
+    ///
+    /// Returns the ids that were deleted, others are not records of the user
+    /// or already deleted.
+    pub async fn del_records(&self, ids: &[i64], user_id: i64) -> Result<Vec<i64>, DbErr> {
+        let transaction = self.db.begin().await?;
+        let user = match self.get_user(&user_id, &transaction).await? {
+            Some(user) => user,
+            None => return Ok(vec![]),
+        };
+
+        let deleted: Vec<i64> = Record::find()
+            .select_only()
+            .column(RecordColumn::Id)
+            .filter(RecordColumn::UserId.eq(user.id))
+            .filter(RecordColumn::DeletedAt.is_null())
+            .filter(RecordColumn::Id.is_in(ids.to_vec()))
+            .into_model::<RecordId>()
+            .all(&transaction)
+            .await?
+            .into_iter()
+            .map(|record| record.id)
+            .collect();
+        if !deleted.is_empty() {
+            Record::update_many()
+                .col_expr(RecordColumn::DeletedAt, Expr::value(now()))
+                .filter(RecordColumn::Id.is_in(deleted.clone()))
+                .exec(&transaction)
+                .await?;
+        }
+
+        transaction.commit().await?;
+        Ok(deleted)
+    }
+
+    /// records of `user_id` not in trash, asked before `/delall`.
+    pub async fn count_user_records(&self, user_id: i64) -> Result<usize, DbErr> {
+        let transaction = self.db.begin().await?;
+        let user = match self.get_user(&user_id, &transaction).await? {
+            Some(user) => user,
+            None => return Ok(0),
+        };
+
+        Record::find()
+            .filter(RecordColumn::UserId.eq(user.id))
+            .filter(RecordColumn::DeletedAt.is_null())
+            .count(&transaction)
+            .await
+    }
+
+    /// move every record of `user_id` to trash when `/delall` is confirmed.
+    ///
+    /// Returns the number of deleted records.
+    pub async fn del_all_records(&self, user_id: i64) -> Result<u64, DbErr> {
+        let transaction = self.db.begin().await?;
+        let user = match self.get_user(&user_id, &transaction).await? {
+            Some(user) => user,
+            None => return Ok(0),
+        };
+
+        let result = Record::update_many()
+            .col_expr(RecordColumn::DeletedAt, Expr::value(now()))
+            .filter(RecordColumn::UserId.eq(user.id))
+            .filter(RecordColumn::DeletedAt.is_null())
+            .exec(&transaction)
+            .await?;
+
+        transaction.commit().await?;
+        Ok(result.rows_affected)
+    }
+
+    /// bring record back from trash when `/restore` command called.
+    ///
+    /// Returns `false` if `user_id` has no such record in trash.
+    pub async fn restore_record(&self, id: i64, user_id: i64) -> Result<bool, DbErr> {
+        self.set_record_deleted_at(id, user_id, None).await
+    }
+
+    async fn set_record_deleted_at(
+        &self,
+        id: i64,
+        user_id: i64,
+        deleted_at: Option<i64>,
+    ) -> Result<bool, DbErr> {
+        let transaction = self.db.begin().await?;
+        let user = match self.get_user(&user_id, &transaction).await? {
+            Some(user) => user,
+            None => return Ok(false),
+        };
+
+        let trashed = match deleted_at {
+            Some(_) => RecordColumn::DeletedAt.is_null(),
+            None => RecordColumn::DeletedAt.is_not_null(),
+        };
+        let record = match Record::find_by_id(id)
+            .filter(RecordColumn::UserId.eq(user.id))
+            .filter(trashed)
+            .one(&transaction)
+            .await?
+        {
+            Some(record) => record,
+            None => return Ok(false),
+        };
+
+        let mut record_active: RecordActiveModel = record.into();
+        record_active.deleted_at = Set(deleted_at);
+        record_active.save(&transaction).await?;
+        transaction.commit().await?;
+        Ok(true)
+    }
+
+    /// recently deleted records of user when `/trash` command called.
+    pub async fn get_trashed_records(&self, user_id: i64) -> Result<Vec<RecordModel>, DbErr> {
+        let transaction = self.db.begin().await?;
+        let user = match self.get_user(&user_id, &transaction).await? {
+            Some(user) => user,
+            None => return Ok(vec![]),
+        };
+
+        Record::find()
+            .filter(RecordColumn::UserId.eq(user.id))
+            .filter(RecordColumn::DeletedAt.is_not_null())
+            .order_by_desc(RecordColumn::DeletedAt)
+            .limit(PAGE_SIZE as u64)
+            .all(&transaction)
+            .await
+    }
+
+    /// permanently delete records trashed more than `days` days ago.
+    ///
+    /// Returns the number of deleted records.
+    pub async fn purge_trash(&self, days: u64) -> Result<u64, DbErr> {
+        let transaction = self.db.begin().await?;
+        let expired = RecordColumn::DeletedAt.lt(now() - days as i64 * SECONDS_PER_DAY);
+
+        let expired_ids = Query::select()
+            .column(RecordColumn::Id)
+            .from(Record)
+            .cond_where(expired.clone())
+            .to_owned();
+
+        RecordTag::delete_many()
+            .filter(RecordTagColumn::RecordId.in_subquery(expired_ids.clone()))
+            .exec(&transaction)
+            .await?;
+        RecordUsage::delete_many()
+            .filter(RecordUsageColumn::RecordId.in_subquery(expired_ids))
+            .exec(&transaction)
+            .await?;
+        let result = Record::delete_many()
+            .filter(expired)
+            .exec(&transaction)
+            .await?;
+
+        transaction.commit().await?;
+        Ok(result.rows_affected)
     }
 
-    pub async fn update_record_hot(&self, id: i64) -> Result<(), DbErr> {
+    /// count a use of the record when `user_id` chose it from inline results.
+    pub async fn update_record_hot(&self, id: i64, user_id: i64) -> Result<(), DbErr> {
         let transcation = self.db.begin().await?;
+        let time = now();
 
         if let Some(record) = Record::find_by_id(id).one(&transcation).await? {
             let hot = record.hot;
+            let trend = add_trend(record.trend, time);
             let mut record_active: RecordActiveModel = record.into();
             record_active.hot = Set(hot + 1);
+            record_active.trend = Set(trend);
             record_active.save(&transcation).await?;
+
+            match RecordUsage::find_by_id((user_id, id))
+                .one(&transcation)
+                .await?
+            {
+                Some(usage) => {
+                    let count = usage.count;
+                    let mut usage_active: RecordUsageActiveModel = usage.into();
+                    usage_active.count = Set(count + 1);
+                    usage_active.used_at = Set(time);
+                    usage_active.update(&transcation).await?;
+                }
+                None => {
+                    RecordUsageActiveModel {
+                        tg_uid: Set(user_id),
+                        record_id: Set(id),
+                        count: Set(1),
+                        used_at: Set(time),
+                    }
+                    .insert(&transcation)
+                    .await?;
+                }
+            }
         }
 
         transcation.commit().await
@@ -207,3 +1967,78 @@ impl Controller {
         }
     }
 }
+
+/// current unix time in seconds.
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+/// `trend` after one more use at `time`.
+///
+/// Both are summed as powers of two, factoring out the larger one keeps the
+/// powers from overflowing.
+fn add_trend(trend: f64, time: i64) -> f64 {
+    let used = time as f64 / TREND_HALF_LIFE as f64;
+    let (high, low) = if trend > used {
+        (trend, used)
+    } else {
+        (used, trend)
+    };
+    high + (low - high).exp2().ln_1p() / std::f64::consts::LN_2
+}
+
+/// uses of a record by one user at `time`, damped and decayed by the last use.
+fn usage_score(count: i64, used_at: i64, time: i64) -> f64 {
+    (count.max(0) as f64).ln_1p() * (-((time - used_at) as f64) / USAGE_HALF_LIFE as f64).exp2()
+}
+
+/// uses of a record at `time` counted with decay, damped so relevance still
+/// matters.
+fn trend_score(trend: f64, time: i64) -> f64 {
+    (trend - time as f64 / TREND_HALF_LIFE as f64)
+        .exp2()
+        .ln_1p()
+}
+
+/// phrase query for the full-text index of `backend`, `None` if `key_word` is
+/// too short for the index.
+fn fulltext_phrase(backend: DbBackend, key_word: &str) -> Option<String> {
+    let key_word = key_word.trim();
+    let length = key_word.chars().count();
+
+    match backend {
+        // the trigram tokenizer needs at least three characters
+        DbBackend::Sqlite if length >= 3 => Some(format!("\"{}\"", key_word.replace('"', "\"\""))),
+        // the ngram parser uses two characters by default
+        DbBackend::MySql if length >= 2 => Some(format!("\"{}\"", key_word.replace('"', " "))),
+        DbBackend::Postgres if length >= 1 => Some(split_cjk(key_word)),
+        _ => None,
+    }
+}
+
+/// put every CJK character apart like the `normalized_tsv` column does.
+fn split_cjk(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\u{3040}'..='\u{30ff}' | '\u{3400}'..='\u{9fff}' | '\u{ac00}'..='\u{d7af}')
+        {
+            output.push(' ');
+            output.push(c);
+            output.push(' ');
+        } else {
+            output.push(c);
+        }
+    }
+    output
+}
+
+/// keep the best relevance of every candidate.
+fn merge_candidates(candidates: &mut HashMap<i64, f64>, other: HashMap<i64, f64>) {
+    for (id, relevance) in other {
+        let best = candidates.entry(id).or_insert(relevance);
+        *best = best.max(relevance);
+    }
+}
@@ -0,0 +1,231 @@
+use std::str::FromStr;
+
+use crate::commands::{format_tags, record_label};
+use crate::formatting::{entities_to_json, utf16_len};
+use crate::search_query::split_tags;
+use chrono::{TimeZone, Utc};
+use models::prelude::{MediaKind, RecordModel, UserModel};
+use sea_orm::ActiveEnum;
+use serde::{Deserialize, Serialize};
+use teloxide::types::MessageEntity;
+
+/// file formats of `/export`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Txt,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(ExportFormat::Json),
+            "csv" => Ok(ExportFormat::Csv),
+            "txt" => Ok(ExportFormat::Txt),
+            _ => Err(format!("unknown export format {}", s)),
+        }
+    }
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Txt => "txt",
+        }
+    }
+}
+
+/// a record as written to an export file, and as read back by `/import`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportRecord {
+    #[serde(default)]
+    pub id: i64,
+    /// Telegram user ID of the quoted user
+    #[serde(default)]
+    pub author_id: Option<i64>,
+    /// username of the quoted user
+    #[serde(default)]
+    pub author: String,
+    pub message: String,
+    /// media kind, as stored in the database, files without it are text
+    #[serde(default = "text_kind")]
+    pub kind: String,
+    #[serde(default)]
+    pub file_id: Option<String>,
+    #[serde(default)]
+    pub file_unique_id: String,
+    /// message entities serialized as JSON
+    #[serde(default)]
+    pub entities: Option<String>,
+    /// `#tag` words separated by spaces
+    #[serde(default)]
+    pub tags: String,
+    #[serde(default)]
+    pub hot: i64,
+    /// unix time the record was saved
+    #[serde(default)]
+    pub created_at: Option<i64>,
+}
+
+impl ExportRecord {
+    pub fn new(record: RecordModel, author: Option<UserModel>, tags: &[String]) -> Self {
+        Self {
+            id: record.id,
+            author_id: author.as_ref().map(|author| author.tg_uid),
+            author: author
+                .and_then(|author| author.username)
+                .unwrap_or_default(),
+            message: record.message,
+            kind: record.kind.to_value(),
+            file_id: record.file_id,
+            file_unique_id: record.file_unique_id,
+            entities: record.entities,
+            tags: format_tags(tags),
+            hot: record.hot,
+            created_at: record.created_at,
+        }
+    }
+
+    /// media kind, records of unknown kinds are text.
+    pub fn media_kind(&self) -> MediaKind {
+        MediaKind::try_from_value(&self.kind).unwrap_or_default()
+    }
+
+    /// entities of an imported record, dropped if they are broken or don't
+    /// fit the message, Telegram refuses any answer showing them otherwise.
+    pub fn checked_entities(&self) -> Option<String> {
+        let entities: Vec<MessageEntity> = serde_json::from_str(self.entities.as_deref()?).ok()?;
+        let length = utf16_len(&self.message);
+        let fits = entities.iter().all(|entity| {
+            entity.length > 0
+                && entity
+                    .offset
+                    .checked_add(entity.length)
+                    .is_some_and(|end| end <= length)
+        });
+
+        match fits {
+            true => entities_to_json(Some(&entities)),
+            false => None,
+        }
+    }
+
+    /// tag names as stored.
+    pub fn tag_names(&self) -> Vec<String> {
+        split_tags(&self.tags).0
+    }
+}
+
+fn text_kind() -> String {
+    MediaKind::Text.to_value()
+}
+
+/// read records of an `/import` file, CSV if the name says so and JSON
+/// otherwise.
+pub fn parse_import(file_name: Option<&str>, data: &[u8]) -> Result<Vec<ExportRecord>, String> {
+    let is_csv = file_name
+        .map(|name| name.to_lowercase().ends_with(".csv"))
+        .unwrap_or_default();
+
+    if is_csv {
+        csv::Reader::from_reader(data)
+            .deserialize()
+            .collect::<Result<Vec<ExportRecord>, csv::Error>>()
+            .map_err(|error| error.to_string())
+    } else {
+        serde_json::from_slice(data).map_err(|error| error.to_string())
+    }
+}
+
+/// write records in `format`.
+pub fn export(records: &[ExportRecord], format: ExportFormat) -> Result<Vec<u8>, String> {
+    match format {
+        ExportFormat::Json => serde_json::to_vec_pretty(records).map_err(|error| error.to_string()),
+        ExportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(vec![]);
+            for record in records {
+                writer
+                    .serialize(record)
+                    .map_err(|error| error.to_string())?;
+            }
+            writer.into_inner().map_err(|error| error.to_string())
+        }
+        ExportFormat::Txt => Ok(records
+            .iter()
+            .map(export_line)
+            .collect::<Vec<String>>()
+            .join("\n")
+            .into_bytes()),
+    }
+}
+
+/// `[date] author: message #tags`, for reading rather than importing.
+fn export_line(record: &ExportRecord) -> String {
+    let mut line = String::new();
+    if let Some(created_at) = record
+        .created_at
+        .and_then(|time| Utc.timestamp_opt(time, 0).single())
+    {
+        line.push_str(&format!("[{}] ", created_at.format("%Y-%m-%d %H:%M")));
+    }
+    line.push_str(&format!(
+        "{}: {}",
+        record.author,
+        record_label(&record.media_kind(), &record.message)
+    ));
+    if !record.tags.is_empty() {
+        line.push_str(&format!(" {}", record.tags));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn import_without_kind_is_text() {
+        let json = parse_import(Some("records.json"), br#"[{"message": "hello"}]"#).unwrap();
+        let csv = parse_import(Some("records.CSV"), b"message,author\nhello,alice\n").unwrap();
+
+        for records in [json, csv] {
+            assert_eq!(records.len(), 1);
+            assert_eq!(records[0].message, "hello");
+            assert_eq!(records[0].media_kind(), MediaKind::Text);
+        }
+    }
+
+    #[test]
+    fn entities_must_fit_message() {
+        let record = |entities: &str| {
+            parse_import(
+                None,
+                format!(
+                    r#"[{{"message": "😀 bold", "entities": {}}}]"#,
+                    serde_json::to_string(entities).unwrap()
+                )
+                .as_bytes(),
+            )
+            .unwrap()
+            .pop()
+            .unwrap()
+        };
+
+        // the emoji takes two UTF-16 code units
+        assert!(record(r#"[{"type": "bold", "offset": 3, "length": 4}]"#)
+            .checked_entities()
+            .is_some());
+        assert!(record(r#"[{"type": "bold", "offset": 3, "length": 5}]"#)
+            .checked_entities()
+            .is_none());
+        assert!(record(r#"[{"type": "unknown"}]"#)
+            .checked_entities()
+            .is_none());
+        assert!(record("not json").checked_entities().is_none());
+    }
+}
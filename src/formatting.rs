@@ -0,0 +1,159 @@
+use teloxide::types::{MessageEntity, MessageEntityKind};
+use wd_log::log_warn_ln;
+
+/// serialize message entities for storing, `None` if there is nothing to keep.
+pub fn entities_to_json(entities: Option<&[MessageEntity]>) -> Option<String> {
+    match entities {
+        Some(entities) if !entities.is_empty() => match serde_json::to_string(entities) {
+            Ok(json) => Some(json),
+            Err(error) => {
+                log_warn_ln!("cannot serialize entities: {}", error);
+                None
+            }
+        },
+        _ => None,
+    }
+}
+
+/// deserialize stored message entities, records without entities are plain text.
+pub fn entities_from_json(json: Option<&str>) -> Vec<MessageEntity> {
+    let json = match json {
+        Some(json) => json,
+        None => return vec![],
+    };
+
+    match serde_json::from_str(json) {
+        Ok(entities) => entities,
+        Err(error) => {
+            log_warn_ln!("cannot deserialize entities: {}", error);
+            vec![]
+        }
+    }
+}
+
+/// length of text in UTF-16 code units, which is what entity offsets count.
+pub fn utf16_len(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+/// move entities right by `by` UTF-16 code units, used when text is prefixed.
+pub fn shift_entities(entities: Vec<MessageEntity>, by: usize) -> Vec<MessageEntity> {
+    entities
+        .into_iter()
+        .map(|mut entity| {
+            entity.offset += by;
+            entity
+        })
+        .collect()
+}
+
+/// rebuild MarkdownV2 text from plain text and its entities.
+pub fn to_markdown_v2(text: &str, entities: &[MessageEntity]) -> String {
+    let mut sorted: Vec<&MessageEntity> =
+        entities.iter().filter(|entity| entity.length > 0).collect();
+    // outer entities first when they start at the same place
+    sorted.sort_by(|a, b| {
+        a.offset
+            .cmp(&b.offset)
+            .then((b.offset + b.length).cmp(&(a.offset + a.length)))
+    });
+
+    let mut output = String::with_capacity(text.len());
+    let mut stack: Vec<&MessageEntity> = vec![];
+    let mut next = 0;
+    let mut pos = 0;
+
+    for c in text.chars() {
+        close_entities(&mut output, &mut stack, pos);
+        while next < sorted.len() && sorted[next].offset <= pos {
+            output.push_str(&open_tag(&sorted[next].kind));
+            stack.push(sorted[next]);
+            next += 1;
+        }
+
+        let in_code = stack.iter().any(|entity| is_code(&entity.kind));
+        if (in_code && (c == '`' || c == '\\')) || (!in_code && is_special(c)) {
+            output.push('\\');
+        }
+        output.push(c);
+
+        pos += c.len_utf16();
+    }
+
+    while let Some(entity) = stack.pop() {
+        output.push_str(&close_tag(&entity.kind));
+    }
+
+    output
+}
+
+/// close every entity ending at `pos`, entities opened after them are reopened.
+fn close_entities(output: &mut String, stack: &mut Vec<&MessageEntity>, pos: usize) {
+    let first_ended = match stack
+        .iter()
+        .position(|entity| entity.offset + entity.length <= pos)
+    {
+        Some(index) => index,
+        None => return,
+    };
+
+    let closed: Vec<&MessageEntity> = stack.drain(first_ended..).collect();
+    for entity in closed.iter().rev() {
+        output.push_str(&close_tag(&entity.kind));
+    }
+    for entity in closed {
+        if entity.offset + entity.length > pos {
+            output.push_str(&open_tag(&entity.kind));
+            stack.push(entity);
+        }
+    }
+}
+
+fn open_tag(kind: &MessageEntityKind) -> String {
+    match kind {
+        MessageEntityKind::Bold => "*".to_string(),
+        MessageEntityKind::Italic => "_".to_string(),
+        MessageEntityKind::Underline => "__".to_string(),
+        MessageEntityKind::Strikethrough => "~".to_string(),
+        MessageEntityKind::Spoiler => "||".to_string(),
+        MessageEntityKind::Code => "`".to_string(),
+        MessageEntityKind::Pre { language } => {
+            format!("```{}\n", language.to_owned().unwrap_or_default())
+        }
+        MessageEntityKind::TextLink { .. } | MessageEntityKind::TextMention { .. } => {
+            "[".to_string()
+        }
+        _ => String::new(),
+    }
+}
+
+fn close_tag(kind: &MessageEntityKind) -> String {
+    match kind {
+        MessageEntityKind::Bold => "*".to_string(),
+        // `\r` keeps italic apart from a following underline, telegram ignores it
+        MessageEntityKind::Italic => "_\r".to_string(),
+        MessageEntityKind::Underline => "__".to_string(),
+        MessageEntityKind::Strikethrough => "~".to_string(),
+        MessageEntityKind::Spoiler => "||".to_string(),
+        MessageEntityKind::Code => "`".to_string(),
+        MessageEntityKind::Pre { .. } => "```".to_string(),
+        MessageEntityKind::TextLink { url } => format!("]({})", escape_url(url.as_str())),
+        MessageEntityKind::TextMention { user } => format!("](tg://user?id={})", user.id),
+        _ => String::new(),
+    }
+}
+
+fn is_code(kind: &MessageEntityKind) -> bool {
+    matches!(
+        kind,
+        MessageEntityKind::Code | MessageEntityKind::Pre { .. }
+    )
+}
+
+fn is_special(c: char) -> bool {
+    "_*[]()~`>#+-=|{}.!\\".contains(c)
+}
+
+fn escape_url(url: &str) -> String {
+    url.replace('\\', "\\\\").replace(')', "\\)")
+}
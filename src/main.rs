@@ -1,12 +1,18 @@
 mod callback_commands;
+mod chat_export;
 mod commands;
 mod config;
 mod db_controller;
+mod export;
+mod formatting;
 mod messages;
+mod search_query;
+mod search_text;
 mod telegram_bot;
 
 use clap::Parser;
-use config::Args;
+use config::{Args, Command};
+use db_controller::Controller;
 use telegram_bot::BotServer;
 use wd_log::{log_debug_ln, log_panic, set_level, set_prefix, DEBUG, INFO};
 
@@ -23,6 +29,24 @@ async fn main() {
         set_level(INFO);
     }
 
+    if let Some(Command::ImportChat {
+        file,
+        authors,
+        recorder,
+    }) = &args.command
+    {
+        let controller = match Controller::new(args.database_uri.to_owned()).await {
+            Ok(controller) => controller,
+            Err(err) => log_panic!("{}", err),
+        };
+        if let Err(err) = controller.migrate().await {
+            controller.err_handler(err);
+        }
+
+        chat_export::import_chat_file(&controller, file, authors, *recorder).await;
+        return;
+    }
+
     let bot = match BotServer::new(args).await {
         Ok(bot) => bot,
         Err(err) => log_panic!("{}", err),
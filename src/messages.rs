@@ -1,4 +1,4 @@
-pub const BOT_TEXT_MESSAGE_ONLY: &'static str = "仅支持文本信息";
+pub const BOT_TEXT_MESSAGE_ONLY: &'static str = "仅支持文本、贴纸、图片、GIF、语音及视频信息";
 pub const BOT_TEXT_FORWARDED_ONLY: &'static str = "仅支持转发信息";
 pub const BOT_TEXT_USER_ONLY: &'static str = "仅支持用户信息";
 pub const BOT_TEXT_USER_PRIVATE: &'static str = "由于用户隐私设置，无法记录此人消息";
@@ -7,7 +7,7 @@ pub const BOT_TEXT_NOTED: &'static str = "✅ `{data}` 已记录";
 pub const BOT_TEXT_NOTICE: &'static str = "[{username}](tg://user?id={user_id}) 转发了你的 `{data}`\n\n\t你可以使用 /list 命令查看自己或者他人被记录的信息\n\t你可以使用 /del 命令删除某条自己的信息\n\t你也可以使用 /mute 或者 /unmute 命令开启或者关闭提醒";
 pub const BOT_TEXT_WELCOME: &'static str =
     "✅ 注册成功！如果有别人记录了你的消息，这里会有提醒，可使用 /mute 命令关闭提醒";
-pub const BOT_HELP: &'static str = "*帮助*\n\n\t/list `[@username]` 列出已记录的内容\n\t/del `id` 删除对应id的记录，只能删除自己的\n\t/mute 关闭提醒\n\t/unmute 开启提醒";
+pub const BOT_HELP: &'static str = "*帮助*\n\n\t/list `[@username] [#标签] [关键词]` 列出已记录的内容\n\t/random `[@username]` 随机发送一条记录\n\t/trending 查看近期热门的记录\n\t/stats 查看统计，群组中为本群的统计\n\t/export `[json|csv|txt] [@username]` 导出记录为文件\n\t/import 导入 /export 导出的 JSON 或 CSV 文件，也可以直接私聊发送文件，Telegram Desktop 导出的 result\\.json 可选择导入某人的消息\n\t/recorded 列出你记录的内容\n\t/del `id…` 删除对应id的记录，可以写多个id或范围如 `3 7 10-15`，只能删除自己的\n\t/delall 删除自己的全部记录\n\t/trash 查看回收站\n\t/restore `id` 从回收站恢复记录\n\t/mute 关闭提醒\n\t/unmute 开启提醒\n\t/scope `all|global` 设置行内搜索及私聊 /list 的范围\n\t/tag `id 标签…` 为记录添加标签，行内搜索时可用 `#标签` 过滤\n\t/optout 拒绝被记录，并隐藏已有记录\n\t/optin 允许被记录\n\t/consent 他人记录的内容需经你同意后才公开\n\t/noconsent 他人记录的内容直接公开\n\n*行内搜索*\n\n\t`from:@username` 只搜索某人的记录\n\t`#标签` 按标签过滤\n\t`\"短语\"` 完整匹配短语\n\t`-关键词` 排除包含关键词的记录\n\t`after:2024-01-01` 只搜索该日期之后的记录\n\t`hot:>10` 按热度过滤";
 pub const BOT_ABOUT: &'static str =
     "Say something bot \\- Reborn\n\n[Github](https://github.com/senseab/saysthbot-reborn) @ssthbot";
 pub const BOT_TEXT_MUTE_STATUS: &'static str = "提醒状态：{status}";
@@ -21,3 +21,73 @@ pub const BOT_BUTTON_PREV: &'static str = "⏪ 上一页";
 pub const BOT_BUTTON_NEXT: &'static str = "下一页 ⏩";
 pub const BOT_TEXT_LOADING: &'static str = "⌛️ 载入中……";
 pub const BOT_TEXT_NO_LIST: &'static str = "没有记录";
+pub const BOT_TEXT_LIST_FILTER_LOST: &'static str = "找不到原来的 /list 消息，请重新发送";
+pub const BOT_TEXT_KIND_STICKER: &'static str = "[贴纸]";
+pub const BOT_TEXT_KIND_PHOTO: &'static str = "[图片]";
+pub const BOT_TEXT_KIND_ANIMATION: &'static str = "[GIF]";
+pub const BOT_TEXT_KIND_VOICE: &'static str = "[语音]";
+pub const BOT_TEXT_KIND_VIDEO: &'static str = "[视频]";
+pub const BOT_TEXT_SCOPE_STATUS: &'static str = "搜索范围：{scope}";
+pub const BOT_TEXT_SCOPE_ALL: &'static str = "全局及所有所在群组的语录库";
+pub const BOT_TEXT_SCOPE_GLOBAL: &'static str = "仅全局语录库";
+pub const BOT_TEXT_TAGGED: &'static str = "🏷 记录 `{id}` 已添加标签：{tags}";
+pub const BOT_TEXT_TAG_USAGE: &'static str = "用法：/tag `id 标签…`";
+pub const BOT_TEXT_RECORD_NOT_FOUND: &'static str = "找不到该记录";
+pub const BOT_TEXT_OPTED_OUT: &'static str = "此人已拒绝被记录";
+pub const BOT_TEXT_OPT_OUT_STATUS: &'static str = "记录状态：{status}";
+pub const BOT_TEXT_RECORDING_ALLOWED: &'static str = "✅ 允许被记录";
+pub const BOT_TEXT_RECORDING_REFUSED: &'static str = "❎ 拒绝被记录，已有记录不再对他人可见";
+pub const BOT_TEXT_NOTED_PENDING: &'static str = "⏳ `{data}` 已记录，待对方同意后公开";
+pub const BOT_TEXT_IMPORT_NOTICE: &'static str = "[{username}](tg://user?id={user_id}) 导入了你的 {count} 条记录\n\n\t你可以使用 /list 命令查看自己或者他人被记录的信息\n\t你可以使用 /del 命令删除某条自己的信息\n\t你也可以使用 /mute 或者 /unmute 命令开启或者关闭提醒";
+pub const BOT_TEXT_PENDING_NOTICE: &'static str = "该记录需要你同意后才会公开";
+pub const BOT_TEXT_CONSENT_STATUS: &'static str = "同意模式：{status}";
+pub const BOT_TEXT_CONSENT_REQUIRED: &'static str = "✅ 他人记录的内容需经你同意后公开";
+pub const BOT_TEXT_CONSENT_NOT_REQUIRED: &'static str = "❎ 他人记录的内容直接公开";
+pub const BOT_BUTTON_APPROVE: &'static str = "✅ 同意";
+pub const BOT_BUTTON_REJECT: &'static str = "🗑 拒绝";
+pub const BOT_TEXT_APPROVED: &'static str = "✅ 已同意公开该记录";
+pub const BOT_TEXT_REJECTED: &'static str = "🗑 已拒绝并删除该记录";
+pub const BOT_TEXT_NOT_PENDING: &'static str = "该记录已处理";
+pub const BOT_TEXT_DUPLICATED: &'static str = "该内容已记录过，记录 id 为 `{id}`";
+pub const BOT_BUTTON_UNDO: &'static str = "↩️ 撤销";
+pub const BOT_TEXT_RESTORED: &'static str = "♻️ 已恢复";
+pub const BOT_TEXT_TRASH: &'static str =
+    "🗑 回收站中的记录会在 {days} 天后永久删除，可使用 /restore `id` 恢复\n\n";
+pub const BOT_TEXT_TRASH_EMPTY: &'static str = "回收站是空的";
+pub const BOT_TEXT_RECORDED_BY: &'static str = " _由 {recorder} 记录_";
+pub const BOT_TEXT_TRENDING: &'static str = "🔥 近期热门\n\n";
+pub const BOT_TEXT_STATS_PERSONAL: &'static str = "📊 共 {total} 条记录，其中 {mine} 条是你的\n";
+pub const BOT_TEXT_STATS_CHAT: &'static str = "📊 本群共 {total} 条记录，其中 {mine} 条是你的\n";
+pub const BOT_TEXT_STATS_TOP_MINE: &'static str = "\n*你最常被使用的记录*\n";
+pub const BOT_TEXT_STATS_TOP_CHAT: &'static str = "\n*本群最常被使用的记录*\n";
+pub const BOT_TEXT_STATS_QUOTED: &'static str = "\n*被记录最多的人*\n";
+pub const BOT_TEXT_STATS_RECORDERS: &'static str = "\n*记录最多的人*\n";
+pub const BOT_TEXT_EXPORT_USAGE: &'static str = "用法：/export `[json|csv|txt] [@username]`";
+pub const BOT_TEXT_IMPORT_USAGE: &'static str =
+    "请在私聊中发送 /export 导出的 JSON 或 CSV 文件，或回复该文件发送 /import，只会导入文字记录";
+pub const BOT_TEXT_IMPORT_PRIVATE_ONLY: &'static str = "请在私聊中导入记录";
+pub const BOT_TEXT_IMPORT_INVALID_FILE: &'static str =
+    "无法读取文件，请发送 /export 导出的 JSON 或 CSV 文件";
+pub const BOT_TEXT_IMPORT_PREVIEW: &'static str =
+    "📥 导入预览\n\n新记录：{added}\n重复：{duplicate}\n无效：{invalid}\n\n确认后才会导入";
+pub const BOT_TEXT_IMPORTED: &'static str =
+    "✅ 已导入\n\n新记录：{added}\n重复：{duplicate}\n无效：{invalid}";
+pub const BOT_TEXT_IMPORT_CANCELLED: &'static str = "已取消导入";
+pub const BOT_BUTTON_IMPORT: &'static str = "✅ 确认导入";
+pub const BOT_BUTTON_CANCEL: &'static str = "❌ 取消";
+pub const BOT_TEXT_CHAT_IMPORT_AUTHORS: &'static str =
+    "📥 聊天记录中共有 {total} 条文字消息，选择要导入谁的消息";
+pub const BOT_TEXT_CHAT_IMPORT_EMPTY: &'static str = "聊天记录中没有可导入的文字消息";
+pub const BOT_TEXT_CHAT_IMPORTING: &'static str = "⏳ 正在导入 {name} 的消息：{done}/{total}";
+pub const BOT_TEXT_CHAT_IMPORTED: &'static str =
+    "✅ 已导入 {name} 的消息：{done}/{total}\n\n新记录：{added}\n重复：{duplicate}\n拒绝被记录：{opted_out}";
+pub const BOT_BUTTON_CHAT_AUTHOR: &'static str = "{name} ({count})";
+pub const BOT_TEXT_DEL_USAGE: &'static str =
+    "用法：/del `id…`，可以写多个id或范围如 `3 7 10-15`，一次最多 {limit} 个";
+pub const BOT_TEXT_DELETED_IDS: &'static str = "已删除：{ids}\n";
+pub const BOT_TEXT_NOT_DELETED_IDS: &'static str = "不是你的记录或已删除：{ids}\n";
+pub const BOT_TEXT_DELETE_ALL_CONFIRM: &'static str =
+    "确定要删除你的全部 {count} 条记录吗？删除后 {days} 天内可在回收站恢复";
+pub const BOT_TEXT_DELETED_ALL: &'static str = "已删除 {count} 条记录";
+pub const BOT_TEXT_DELETE_ALL_CANCELLED: &'static str = "已取消删除";
+pub const BOT_BUTTON_DELETE_ALL: &'static str = "🗑 全部删除";
@@ -0,0 +1,228 @@
+use chrono::NaiveDate;
+
+/// comparison of `hot:` filters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HotFilter {
+    Greater(i64),
+    GreaterOrEqual(i64),
+    Less(i64),
+    LessOrEqual(i64),
+    Equal(i64),
+}
+
+/// inline query parsed into its filters.
+///
+/// Anything that isn't valid syntax is kept as a plain keyword.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SearchQuery {
+    /// plain keywords
+    pub keywords: String,
+    /// `"exact phrase"`
+    pub phrases: Vec<String>,
+    /// `-exclude`
+    pub excludes: Vec<String>,
+    /// `#tag`
+    pub tags: Vec<String>,
+    /// `from:@alice`
+    pub from: Option<String>,
+    /// `after:2024-01-01`, unix time
+    pub after: Option<i64>,
+    /// `hot:>10`
+    pub hot: Option<HotFilter>,
+}
+
+impl SearchQuery {
+    pub fn parse(input: &str) -> Self {
+        let mut query = Self::default();
+        let mut keywords: Vec<&str> = vec![];
+        let mut rest = input.trim_start();
+
+        while !rest.is_empty() {
+            if let Some(quoted) = rest.strip_prefix('"') {
+                if let Some((phrase, remain)) = quoted.split_once('"') {
+                    if !phrase.trim().is_empty() {
+                        query.phrases.push(phrase.trim().to_string());
+                    }
+                    rest = remain.trim_start();
+                    continue;
+                }
+                // an unbalanced quote is plain text
+            }
+
+            let (word, remain) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            if !query.parse_filter(word) {
+                keywords.push(word);
+            }
+            rest = remain.trim_start();
+        }
+
+        query.keywords = keywords.join(" ");
+        query
+    }
+
+    /// text to rank matches by, the phrases if there are no keywords.
+    pub fn search_text(&self) -> String {
+        if self.keywords.is_empty() {
+            self.phrases.join(" ")
+        } else {
+            self.keywords.to_owned()
+        }
+    }
+
+    /// apply `word` if it is a filter, `false` if it is a plain keyword.
+    fn parse_filter(&mut self, word: &str) -> bool {
+        if word.starts_with('#') {
+            if let Some(tag) = normalize_tag(word) {
+                if !self.tags.contains(&tag) {
+                    self.tags.push(tag);
+                }
+                return true;
+            }
+        } else if let Some(exclude) = word.strip_prefix('-') {
+            if !exclude.is_empty() {
+                self.excludes.push(exclude.to_string());
+                return true;
+            }
+        } else if let Some(from) = word.strip_prefix("from:") {
+            let from = from.trim_start_matches('@');
+            if !from.is_empty() {
+                self.from = Some(format!("@{}", from));
+                return true;
+            }
+        } else if let Some(date) = word.strip_prefix("after:") {
+            if let Some(time) = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+            {
+                self.after = Some(time.and_utc().timestamp());
+                return true;
+            }
+        } else if let Some(hot) = word.strip_prefix("hot:") {
+            if let Some(hot) = parse_hot(hot) {
+                self.hot = Some(hot);
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+/// tag name as stored, lowercase without the leading `#`.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim_start_matches("#").to_lowercase();
+    if tag.is_empty() {
+        None
+    } else {
+        Some(tag)
+    }
+}
+
+/// split `#tag` words out of a query, returns the tags and the remaining keywords.
+pub fn split_tags(input: &str) -> (Vec<String>, String) {
+    let mut tags: Vec<String> = vec![];
+    let mut keywords: Vec<&str> = vec![];
+
+    for word in input.split_whitespace() {
+        if !word.starts_with("#") {
+            keywords.push(word);
+        } else if let Some(tag) = normalize_tag(word) {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+    }
+
+    (tags, keywords.join(" "))
+}
+
+fn parse_hot(input: &str) -> Option<HotFilter> {
+    let (filter, value): (fn(i64) -> HotFilter, &str) =
+        if let Some(value) = input.strip_prefix(">=") {
+            (HotFilter::GreaterOrEqual, value)
+        } else if let Some(value) = input.strip_prefix("<=") {
+            (HotFilter::LessOrEqual, value)
+        } else if let Some(value) = input.strip_prefix('>') {
+            (HotFilter::Greater, value)
+        } else if let Some(value) = input.strip_prefix('<') {
+            (HotFilter::Less, value)
+        } else {
+            (HotFilter::Equal, input.strip_prefix('=').unwrap_or(input))
+        };
+
+    value.parse::<i64>().ok().map(filter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_filters() {
+        let query = SearchQuery::parse(
+            r#"hello "exact phrase" -spam #Tag from:@alice after:2024-01-01 hot:>10 world"#,
+        );
+
+        assert_eq!(query.keywords, "hello world");
+        assert_eq!(query.phrases, vec!["exact phrase"]);
+        assert_eq!(query.excludes, vec!["spam"]);
+        assert_eq!(query.tags, vec!["tag"]);
+        assert_eq!(query.from.as_deref(), Some("@alice"));
+        assert_eq!(query.after, Some(1704067200));
+        assert_eq!(query.hot, Some(HotFilter::Greater(10)));
+    }
+
+    #[test]
+    fn unbalanced_quote_is_text() {
+        let query = SearchQuery::parse(r#""hello world"#);
+
+        assert_eq!(query.keywords, r#""hello world"#);
+        assert!(query.phrases.is_empty());
+    }
+
+    #[test]
+    fn lone_minus_is_text() {
+        let query = SearchQuery::parse("a - b");
+
+        assert_eq!(query.keywords, "a - b");
+        assert!(query.excludes.is_empty());
+    }
+
+    #[test]
+    fn invalid_after_is_text() {
+        let query = SearchQuery::parse("after:2024-13-01");
+
+        assert_eq!(query.keywords, "after:2024-13-01");
+        assert_eq!(query.after, None);
+    }
+
+    #[test]
+    fn hot_comparisons() {
+        assert_eq!(
+            SearchQuery::parse("hot:>=10").hot,
+            Some(HotFilter::GreaterOrEqual(10))
+        );
+        assert_eq!(
+            SearchQuery::parse("hot:<=3").hot,
+            Some(HotFilter::LessOrEqual(3))
+        );
+        assert_eq!(SearchQuery::parse("hot:5").hot, Some(HotFilter::Equal(5)));
+        assert_eq!(SearchQuery::parse("hot:>x").hot, None);
+    }
+
+    #[test]
+    fn from_without_name_is_text() {
+        let query = SearchQuery::parse("from: from:@");
+
+        assert_eq!(query.keywords, "from: from:@");
+        assert_eq!(query.from, None);
+    }
+
+    #[test]
+    fn split_tags_keeps_keywords() {
+        assert_eq!(
+            split_tags("#Cat hello #cat # world"),
+            (vec!["cat".to_string()], "hello world".to_string())
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashSet;
+
+use pinyin::ToPinyin;
+use zhconv::{zhconv, Variant};
+
+/// shortest query matched in pinyin, pinyin is stored without syllable
+/// boundaries so shorter ones match across syllables
+const PINYIN_MIN_LEN: usize = 3;
+
+/// fold `text` for matching: simplified script, half width and lowercase.
+pub fn normalize(text: &str) -> String {
+    zhconv(text, Variant::ZhHans)
+        .chars()
+        .map(fold_width)
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// lowercase letters and digits of `text`, what pinyin queries are matched on.
+pub fn compact(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// full pinyin of `text` without tones or spaces, e.g. `nihao` for `你好`.
+///
+/// Other letters and digits are kept as they are.
+pub fn pinyin(text: &str) -> String {
+    spell(text, |pinyin| pinyin.plain())
+}
+
+/// pinyin initials of `text`, e.g. `nh` for `你好`.
+pub fn initials(text: &str) -> String {
+    spell(text, |pinyin| pinyin.first_letter())
+}
+
+/// pinyin query of `key_word`, `None` if it cannot be pinyin or is too short.
+pub fn pinyin_query(key_word: &str) -> Option<String> {
+    let query = compact(key_word);
+    if query.len() >= PINYIN_MIN_LEN && query.chars().all(|c| c.is_ascii_alphanumeric()) {
+        Some(query)
+    } else {
+        None
+    }
+}
+
+fn spell(text: &str, form: fn(pinyin::Pinyin) -> &'static str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for (c, pinyin) in text.chars().zip(text.to_pinyin()) {
+        match pinyin {
+            Some(pinyin) => output.push_str(form(pinyin)),
+            None if c.is_alphanumeric() => output.extend(c.to_lowercase()),
+            None => (),
+        }
+    }
+    output
+}
+
+/// full width forms to their ASCII counterparts.
+fn fold_width(c: char) -> char {
+    match c {
+        '\u{3000}' => ' ',
+        '\u{ff01}'..='\u{ff5e}' => char::from_u32(c as u32 - 0xfee0).unwrap_or(c),
+        c => c,
+    }
+}
+
+/// share of the trigrams of `query` found in `text`, from 0 to 1.
+///
+/// Like `word_similarity` of `pg_trgm`, a short query is similar to a long
+/// text containing it.
+pub fn trigram_similarity(query: &str, text: &str) -> f64 {
+    let query = trigrams(query);
+    if query.is_empty() {
+        return 0.0;
+    }
+
+    let text = trigrams(text);
+    query.intersection(&text).count() as f64 / query.len() as f64
+}
+
+/// trigrams of every word padded with spaces, like `pg_trgm` does.
+fn trigrams(text: &str) -> HashSet<[char; 3]> {
+    let mut trigrams = HashSet::new();
+    for word in text.split_whitespace() {
+        let padded: Vec<char> = "  "
+            .chars()
+            .chain(word.chars())
+            .chain(" ".chars())
+            .collect();
+        for window in padded.windows(3) {
+            trigrams.insert([window[0], window[1], window[2]]);
+        }
+    }
+    trigrams
+}
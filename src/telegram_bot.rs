@@ -1,37 +1,80 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use crate::callback_commands::CallbackCommands;
-use crate::db_controller::Controller;
+use crate::chat_export::{import_messages, ChatExport, ChatImportReport, IMPORT_BATCH};
+use crate::db_controller::{AddRecordResult, Controller, RecordContent};
+use crate::export::{parse_import, ExportRecord};
+use crate::formatting::{entities_from_json, entities_to_json, shift_entities, utf16_len};
 use crate::messages::*;
-use crate::{commands::CommandHandler, commands::Commands, config::Args};
+use crate::search_query::SearchQuery;
+use crate::{
+    commands::chat_library, commands::record_label, commands::CommandHandler, commands::Commands,
+    config::Args,
+};
 use migration::DbErr;
+use models::prelude::{MediaKind, RecordModel};
 use strfmt::Format;
 
-use teloxide::utils::{command::BotCommands, markdown::escape};
+use teloxide::net::Download;
+use teloxide::utils::{
+    command::{parse_command, BotCommands, ParseError},
+    markdown::escape,
+};
 use teloxide::{
-    prelude::*, types::ForwardedFrom, types::InlineQueryResult, types::InlineQueryResultArticle,
-    types::InputMessageContent, types::InputMessageContentText, types::ParseMode,
-    types::ReplyMarkup, types::UpdateKind, RequestError,
+    prelude::*, types::ForwardedFrom, types::InlineKeyboardMarkup, types::InlineQueryResult,
+    types::InlineQueryResultArticle, types::InlineQueryResultCachedGif,
+    types::InlineQueryResultCachedPhoto, types::InlineQueryResultCachedSticker,
+    types::InlineQueryResultCachedVideo, types::InlineQueryResultCachedVoice, types::InputFile,
+    types::InputMessageContent, types::InputMessageContentText, types::MessageEntity,
+    types::MessageEntityKind, types::ParseMode, types::ReplyMarkup, types::UpdateKind, types::User,
+    RequestError,
 };
 use wd_log::{log_debug_ln, log_error_ln, log_info_ln, log_panic, log_warn_ln};
 
+const TRASH_PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// name used to find a user, `@username` if there is one.
+pub fn display_username(user: &User) -> String {
+    match &user.username {
+        Some(username) => format!("@{}", username),
+        None => user.first_name.to_owned(),
+    }
+}
+
+#[derive(Clone)]
 pub struct BotServer {
     pub controller: Controller,
+    /// days before deleted records are purged from trash
+    pub trash_days: u64,
     bot: Bot,
+    /// (user, chat) pairs already saved as memberships, so messages of known
+    /// members don't write
+    members: Arc<Mutex<HashSet<(i64, i64)>>>,
 }
 
 impl BotServer {
     /// Create new bot
     pub async fn new(config: Args) -> Result<Self, DbErr> {
+        let token = match config.tgbot_token {
+            Some(token) => token,
+            None => log_panic!("the Telegram bot token is required, set TGBOT_TOKEN"),
+        };
+
         Ok(Self {
-            bot: Bot::new(config.tgbot_token)
-                .set_api_url(reqwest::Url::parse(config.api_url.as_str()).unwrap()),
+            bot: Bot::new(token).set_api_url(reqwest::Url::parse(config.api_url.as_str()).unwrap()),
             controller: Controller::new(config.database_uri).await?,
+            trash_days: config.trash_days,
+            members: Arc::new(Mutex::new(HashSet::new())),
         })
     }
 
     pub async fn init(&self) -> Result<(), DbErr> {
-        self.controller.migrate().await
+        self.controller.migrate().await?;
+        self.controller.backfill_search_text().await
     }
 
     /// Run the bot
@@ -46,6 +89,7 @@ impl BotServer {
         }
 
         self.register_commands().await;
+        self.spawn_trash_purger();
 
         let mut offset_id = 0;
 
@@ -61,6 +105,23 @@ impl BotServer {
         }
     }
 
+    /// purge expired records from trash every hour in background.
+    fn spawn_trash_purger(&self) {
+        let controller = self.controller.clone();
+        let trash_days = self.trash_days;
+
+        tokio::spawn(async move {
+            loop {
+                match controller.purge_trash(trash_days).await {
+                    Ok(0) => (),
+                    Ok(count) => log_info_ln!("{} records purged from trash", count),
+                    Err(error) => controller.err_handler(error),
+                }
+                tokio::time::sleep(TRASH_PURGE_INTERVAL).await;
+            }
+        });
+    }
+
     async fn register_commands(&self) {
         if let Err(error) = self
             .bot
@@ -95,7 +156,10 @@ impl BotServer {
 
         if let Err(error) = self
             .controller
-            .update_record_hot(result.result_id.parse::<i64>().unwrap())
+            .update_record_hot(
+                result.result_id.parse::<i64>().unwrap(),
+                result.from.id.0.try_into().unwrap(),
+            )
             .await
         {
             self.controller.err_handler(error);
@@ -128,7 +192,7 @@ impl BotServer {
             None => return,
         };
 
-        let commands = match CallbackCommands::parse(text, bot_username) {
+        let commands = match CallbackCommands::parse(text, &bot_username) {
             Ok(c) => c,
             Err(error) => {
                 log_warn_ln!("{}", error);
@@ -141,11 +205,37 @@ impl BotServer {
                 msg_id: _,
                 username,
                 page,
+                filtered,
             } => {
+                // the list replies to the `/list` message, which has the filter
+                let filter = match message
+                    .reply_to_message()
+                    .and_then(|list| list.text())
+                    .map(|list| Commands::parse(list, &bot_username))
+                {
+                    Some(Ok(Commands::List { filter, .. })) => filter,
+                    _ if !filtered => String::new(),
+                    _ => {
+                        // paging without the filter would list something else
+                        if let Err(error) = self
+                            .bot
+                            .answer_callback_query(&callback.id)
+                            .text(BOT_TEXT_LIST_FILTER_LOST)
+                            .send()
+                            .await
+                        {
+                            self.default_error_handler(&error);
+                        }
+                        return;
+                    }
+                };
+
                 let (msg, keyboard) = match CommandHandler::record_msg_genrator(
                     self,
                     message,
+                    callback.from.id.0.try_into().unwrap(),
                     username.as_str(),
+                    filter.as_str(),
                     page,
                 )
                 .await
@@ -162,14 +252,398 @@ impl BotServer {
                     Err(error) => self.default_error_handler(&error),
                 }
             }
+            CallbackCommands::Recorded { recorder, page } => {
+                let (msg, keyboard) = match CommandHandler::recorded_msg_genrator(
+                    self,
+                    message,
+                    recorder,
+                    callback.from.id.0.try_into().unwrap(),
+                    page,
+                )
+                .await
+                {
+                    Some(d) => d,
+                    None => return,
+                };
+
+                self.edit_text_reply_with_inline_key(message, message.id, msg.as_str(), keyboard)
+                    .await;
+
+                match self.bot.answer_callback_query(&callback.id).send().await {
+                    Ok(_) => (),
+                    Err(error) => self.default_error_handler(&error),
+                }
+            }
+            CallbackCommands::Approve { id } => {
+                self.review_handler(callback, message, id, true).await
+            }
+            CallbackCommands::Reject { id } => {
+                self.review_handler(callback, message, id, false).await
+            }
+            CallbackCommands::Undo { id } => {
+                match self
+                    .controller
+                    .restore_record(id, callback.from.id.0.try_into().unwrap())
+                    .await
+                {
+                    Ok(true) => {
+                        self.edit_text_reply_with_inline_key(
+                            message,
+                            message.id,
+                            BOT_TEXT_RESTORED,
+                            ReplyMarkup::InlineKeyboard(InlineKeyboardMarkup {
+                                inline_keyboard: vec![],
+                            }),
+                        )
+                        .await
+                    }
+                    Ok(false) => (),
+                    Err(error) => {
+                        self.controller.err_handler(error);
+                        return;
+                    }
+                }
+
+                match self.bot.answer_callback_query(&callback.id).send().await {
+                    Ok(_) => (),
+                    Err(error) => self.default_error_handler(&error),
+                }
+            }
+            CallbackCommands::Import { confirm } => {
+                self.import_callback_handler(callback, message, confirm)
+                    .await
+            }
+            CallbackCommands::ChatImport { author } => {
+                self.chat_import_callback_handler(callback, message, author)
+                    .await
+            }
+            CallbackCommands::DeleteAll => {
+                self.delete_all_callback_handler(callback, message, true)
+                    .await
+            }
+            CallbackCommands::CancelDeleteAll => {
+                self.delete_all_callback_handler(callback, message, false)
+                    .await
+            }
             CallbackCommands::Default => return,
         }
     }
 
+    /// approve or reject a pending record from the notice buttons.
+    async fn review_handler(
+        &self,
+        callback: &CallbackQuery,
+        message: &Message,
+        id: i64,
+        approve: bool,
+    ) {
+        let status = match self
+            .controller
+            .review_record(id, callback.from.id.0.try_into().unwrap(), approve)
+            .await
+        {
+            Ok(true) if approve => BOT_TEXT_APPROVED,
+            Ok(true) => BOT_TEXT_REJECTED,
+            Ok(false) => BOT_TEXT_NOT_PENDING,
+            Err(error) => {
+                self.controller.err_handler(error);
+                return;
+            }
+        };
+
+        let text = match message.text() {
+            Some(text) => format!("{}\n\n{}", text, status),
+            None => status.to_string(),
+        };
+
+        match self
+            .bot
+            .edit_message_text(message.chat.id, message.id, text)
+            .send()
+            .await
+        {
+            Ok(result) => log_debug_ln!("reply sent {:?}", result),
+            Err(error) => self.default_error_handler(&error),
+        }
+
+        match self.bot.answer_callback_query(&callback.id).send().await {
+            Ok(_) => (),
+            Err(error) => self.default_error_handler(&error),
+        }
+    }
+
+    /// delete all records of the user, or cancel it, from the `/delall` buttons.
+    async fn delete_all_callback_handler(
+        &self,
+        callback: &CallbackQuery,
+        message: &Message,
+        confirm: bool,
+    ) {
+        // only who sent `/delall` can answer it
+        let asked_by = message
+            .reply_to_message()
+            .and_then(|command| command.from())
+            .map(|user| user.id);
+        if asked_by == Some(callback.from.id) {
+            let text = if !confirm {
+                BOT_TEXT_DELETE_ALL_CANCELLED.to_string()
+            } else {
+                match self
+                    .controller
+                    .del_all_records(callback.from.id.0.try_into().unwrap())
+                    .await
+                {
+                    Ok(count) => {
+                        let mut vars = HashMap::new();
+                        vars.insert("count".to_string(), count.to_string());
+                        BOT_TEXT_DELETED_ALL.format(&vars).unwrap()
+                    }
+                    Err(error) => {
+                        self.controller.err_handler(error);
+                        return;
+                    }
+                }
+            };
+
+            self.edit_text_reply_with_inline_key(
+                message,
+                message.id,
+                &text,
+                ReplyMarkup::InlineKeyboard(InlineKeyboardMarkup {
+                    inline_keyboard: vec![],
+                }),
+            )
+            .await;
+        }
+
+        match self.bot.answer_callback_query(&callback.id).send().await {
+            Ok(_) => (),
+            Err(error) => self.default_error_handler(&error),
+        }
+    }
+
+    /// import the document of a preview, or cancel it, from its buttons.
+    async fn import_callback_handler(
+        &self,
+        callback: &CallbackQuery,
+        message: &Message,
+        confirm: bool,
+    ) {
+        let document_message = match message.reply_to_message() {
+            Some(reply) if reply.document().is_some() => reply,
+            _ => return,
+        };
+
+        // only who sent the file can import it
+        if document_message.from().map(|user| user.id) == Some(callback.from.id) {
+            let text = if !confirm {
+                BOT_TEXT_IMPORT_CANCELLED.to_string()
+            } else {
+                match self.read_import_file(document_message).await {
+                    Some(records) => match self
+                        .controller
+                        .import_records(
+                            callback.from.id.0.try_into().unwrap(),
+                            &display_username(&callback.from),
+                            records,
+                            false,
+                        )
+                        .await
+                    {
+                        Ok(summary) => {
+                            // the file is not proof of anything, authors have to know
+                            self.send_import_notices(&callback.from, &summary.records)
+                                .await;
+                            CommandHandler::import_summary_msg(BOT_TEXT_IMPORTED, &summary)
+                        }
+                        Err(error) => {
+                            self.controller.err_handler(error);
+                            return;
+                        }
+                    },
+                    None => BOT_TEXT_IMPORT_INVALID_FILE.to_string(),
+                }
+            };
+
+            self.edit_text_reply_with_inline_key(
+                message,
+                message.id,
+                &text,
+                ReplyMarkup::InlineKeyboard(InlineKeyboardMarkup {
+                    inline_keyboard: vec![],
+                }),
+            )
+            .await;
+        }
+
+        match self.bot.answer_callback_query(&callback.id).send().await {
+            Ok(_) => (),
+            Err(error) => self.default_error_handler(&error),
+        }
+    }
+
+    /// download and parse the document of `message` for `/import`.
+    pub async fn read_import_file(&self, message: &Message) -> Option<Vec<ExportRecord>> {
+        let data = self.download_document(message).await?;
+
+        match parse_import(message.document()?.file_name.as_deref(), &data) {
+            Ok(records) => Some(records),
+            Err(error) => {
+                log_warn_ln!("cannot parse import file: {}", error);
+                None
+            }
+        }
+    }
+
+    /// download the document of `message`.
+    pub async fn download_document(&self, message: &Message) -> Option<Vec<u8>> {
+        let document = message.document()?;
+        let file = match self.bot.get_file(&document.file_id).send().await {
+            Ok(file) => file,
+            Err(error) => {
+                self.default_error_handler(&error);
+                return None;
+            }
+        };
+
+        let mut data: Vec<u8> = vec![];
+        match self.bot.download_file(&file.file_path, &mut data).await {
+            Ok(_) => Some(data),
+            Err(error) => {
+                log_warn_ln!("cannot download {}: {}", file.file_path, error);
+                None
+            }
+        }
+    }
+
+    /// import messages of an author from a chat export, from the author
+    /// buttons of its preview.
+    async fn chat_import_callback_handler(
+        &self,
+        callback: &CallbackQuery,
+        message: &Message,
+        author: i64,
+    ) {
+        let document_message = match message.reply_to_message() {
+            Some(reply) if reply.document().is_some() => reply,
+            _ => return,
+        };
+
+        // only who sent the file can import it
+        if document_message.from().map(|user| user.id) != Some(callback.from.id) {
+            match self.bot.answer_callback_query(&callback.id).send().await {
+                Ok(_) => (),
+                Err(error) => self.default_error_handler(&error),
+            }
+            return;
+        }
+
+        if let Err(error) = self.bot.answer_callback_query(&callback.id).send().await {
+            self.default_error_handler(&error);
+        }
+
+        let chat = match self
+            .download_document(document_message)
+            .await
+            .map(|data| ChatExport::parse(&data))
+        {
+            Some(Ok(chat)) => chat,
+            _ => {
+                self.edit_text_reply_with_inline_key(
+                    message,
+                    message.id,
+                    BOT_TEXT_IMPORT_INVALID_FILE,
+                    ReplyMarkup::InlineKeyboard(InlineKeyboardMarkup {
+                        inline_keyboard: vec![],
+                    }),
+                )
+                .await;
+                return;
+            }
+        };
+
+        // big exports take a while, other updates are handled meanwhile
+        let bot_s = self.clone();
+        let message = message.clone();
+        let recorder = callback.from.clone();
+        tokio::spawn(async move {
+            bot_s
+                .import_chat_messages(&message, &chat, author, &recorder)
+                .await;
+        });
+    }
+
+    /// import messages of `author` from `chat` as recorded by `recorder`,
+    /// reporting progress by editing `message`.
+    async fn import_chat_messages(
+        &self,
+        message: &Message,
+        chat: &ChatExport,
+        author: i64,
+        recorder: &User,
+    ) {
+        let name = chat
+            .authors()
+            .into_iter()
+            .find(|(id, _, _)| *id == author)
+            .map(|(_, name, _)| name)
+            .unwrap_or_else(|| author.to_string());
+        let messages = chat.messages_of(&[author]);
+        let recorder_name = display_username(recorder);
+        let mut report = ChatImportReport {
+            total: messages.len(),
+            ..Default::default()
+        };
+        let mut added = vec![];
+
+        for batch in messages.chunks(IMPORT_BATCH) {
+            self.edit_text_reply_with_inline_key(
+                message,
+                message.id,
+                &CommandHandler::chat_import_report_msg(BOT_TEXT_CHAT_IMPORTING, &name, &report),
+                ReplyMarkup::InlineKeyboard(InlineKeyboardMarkup {
+                    inline_keyboard: vec![],
+                }),
+            )
+            .await;
+
+            match import_messages(
+                &self.controller,
+                batch,
+                recorder.id.0.try_into().unwrap(),
+                Some(&recorder_name),
+                &mut report,
+            )
+            .await
+            {
+                Ok(records) => added.extend(records),
+                Err(error) => {
+                    self.controller.err_handler(error);
+                    break;
+                }
+            }
+        }
+
+        // the other authors can still be imported
+        self.edit_text_reply_with_inline_key(
+            message,
+            message.id,
+            &CommandHandler::chat_import_report_msg(BOT_TEXT_CHAT_IMPORTED, &name, &report),
+            CommandHandler::generate_chat_import_keyboard(chat),
+        )
+        .await;
+
+        // the file is not proof of anything, authors have to know
+        self.send_import_notices(recorder, &added).await;
+    }
+
     async fn inline_query_hander(&self, inline_query: &InlineQuery) {
+        let query = SearchQuery::parse(&inline_query.query);
+        // the offset is the page number we sent as `next_offset`
+        let page = inline_query.offset.parse::<usize>().unwrap_or(0);
         let results = match self
             .controller
-            .get_records_by_keywords(&inline_query.query)
+            .get_records_by_keywords(&query, inline_query.from.id.0.try_into().unwrap(), page)
             .await
         {
             Ok(results) => results,
@@ -191,32 +665,20 @@ impl BotServer {
                 None => continue,
             };
 
-            r.push(InlineQueryResult::Article(InlineQueryResultArticle {
-                id: record.id.to_string(),
-                title: record.message.to_owned(),
-                input_message_content: InputMessageContent::Text(InputMessageContentText {
-                    message_text: format!(
-                        "*{}*: {}",
-                        username.trim_start_matches("@"),
-                        escape(&record.message)
-                    ),
-                    parse_mode: Some(ParseMode::MarkdownV2),
-                    entities: None,
-                    disable_web_page_preview: Some(true),
-                }),
-                reply_markup: None,
-                url: None,
-                hide_url: None,
-                description: Some(format!("By: {}", username)),
-                thumb_url: None,
-                thumb_width: None,
-                thumb_height: None,
-            }));
+            r.push(Self::record_inline_result(record, username));
         }
 
+        let next_offset = match page + 1 < results.pages_count {
+            true => (page + 1).to_string(),
+            false => String::new(),
+        };
+
         if let Err(error) = self
             .bot
             .answer_inline_query(&inline_query.id, r.into_iter())
+            // results depend on who is asking
+            .is_personal(true)
+            .next_offset(next_offset)
             .send()
             .await
         {
@@ -224,21 +686,296 @@ impl BotServer {
         }
     }
 
+    /// build the inline result of a record, keeping its original formatting.
+    fn record_inline_result(record: &RecordModel, username: &str) -> InlineQueryResult {
+        let id = record.id.to_string();
+        let file_id = record.file_id.to_owned().unwrap_or_default();
+        let caption = record.message.to_owned();
+        let entities = entities_from_json(record.entities.as_deref());
+
+        match record.kind {
+            MediaKind::Sticker => {
+                InlineQueryResult::CachedSticker(InlineQueryResultCachedSticker::new(id, file_id))
+            }
+            MediaKind::Photo => {
+                let mut result = InlineQueryResultCachedPhoto::new(id, file_id);
+                if !caption.is_empty() {
+                    result = result.caption(caption).caption_entities(entities);
+                }
+                InlineQueryResult::CachedPhoto(result)
+            }
+            MediaKind::Animation => {
+                let mut result = InlineQueryResultCachedGif::new(id, file_id);
+                if !caption.is_empty() {
+                    result = result.caption(caption).caption_entities(entities);
+                }
+                InlineQueryResult::CachedGif(result)
+            }
+            MediaKind::Voice => {
+                let mut result =
+                    InlineQueryResultCachedVoice::new(id, file_id, format!("By: {}", username));
+                if !caption.is_empty() {
+                    result = result.caption(caption).caption_entities(entities);
+                }
+                InlineQueryResult::CachedVoice(result)
+            }
+            MediaKind::Video => {
+                let mut result =
+                    InlineQueryResultCachedVideo::new(id, file_id, format!("By: {}", username));
+                if !caption.is_empty() {
+                    result = result
+                        .description(caption.to_owned())
+                        .caption(caption)
+                        .caption_entities(entities);
+                }
+                InlineQueryResult::CachedVideo(result)
+            }
+            MediaKind::Text => {
+                let (message_text, message_entities) = Self::quote_text(record, username);
+
+                InlineQueryResult::Article(InlineQueryResultArticle {
+                    id,
+                    title: record.message.to_owned(),
+                    input_message_content: InputMessageContent::Text(InputMessageContentText {
+                        message_text,
+                        parse_mode: None,
+                        entities: Some(message_entities),
+                        disable_web_page_preview: Some(true),
+                    }),
+                    reply_markup: None,
+                    url: None,
+                    hide_url: None,
+                    description: Some(format!("By: {}", username)),
+                    thumb_url: None,
+                    thumb_width: None,
+                    thumb_height: None,
+                })
+            }
+        }
+    }
+
+    /// "name: message", with the name in bold and the original entities after it.
+    fn quote_text(record: &RecordModel, username: &str) -> (String, Vec<MessageEntity>) {
+        let name = username.trim_start_matches("@");
+        let prefix_len = utf16_len(name);
+        let mut entities = vec![MessageEntity {
+            kind: MessageEntityKind::Bold,
+            offset: 0,
+            length: prefix_len,
+        }];
+        entities.extend(shift_entities(
+            entities_from_json(record.entities.as_deref()),
+            prefix_len + 2,
+        ));
+
+        (format!("{}: {}", name, record.message), entities)
+    }
+
+    /// post a record as a reply to `message`, media is sent as it was recorded.
+    pub async fn send_record(&self, message: &Message, record: &RecordModel, username: &str) {
+        let chat_id = message.chat.id;
+        let file = InputFile::file_id(record.file_id.to_owned().unwrap_or_default());
+        let caption = record.message.to_owned();
+        let entities = entities_from_json(record.entities.as_deref());
+
+        let result = match record.kind {
+            MediaKind::Text => {
+                let (text, entities) = Self::quote_text(record, username);
+                self.bot
+                    .send_message(chat_id, text)
+                    .entities(entities)
+                    .disable_web_page_preview(true)
+                    .reply_to_message_id(message.id)
+                    .send()
+                    .await
+            }
+            MediaKind::Sticker => {
+                self.bot
+                    .send_sticker(chat_id, file)
+                    .reply_to_message_id(message.id)
+                    .send()
+                    .await
+            }
+            MediaKind::Photo => {
+                self.bot
+                    .send_photo(chat_id, file)
+                    .caption(caption)
+                    .caption_entities(entities)
+                    .reply_to_message_id(message.id)
+                    .send()
+                    .await
+            }
+            MediaKind::Animation => {
+                self.bot
+                    .send_animation(chat_id, file)
+                    .caption(caption)
+                    .caption_entities(entities)
+                    .reply_to_message_id(message.id)
+                    .send()
+                    .await
+            }
+            MediaKind::Voice => {
+                self.bot
+                    .send_voice(chat_id, file)
+                    .caption(caption)
+                    .caption_entities(entities)
+                    .reply_to_message_id(message.id)
+                    .send()
+                    .await
+            }
+            MediaKind::Video => {
+                self.bot
+                    .send_video(chat_id, file)
+                    .caption(caption)
+                    .caption_entities(entities)
+                    .reply_to_message_id(message.id)
+                    .send()
+                    .await
+            }
+        };
+
+        match result {
+            Ok(result) => log_debug_ln!("record sent {:?}", result),
+            Err(error) => self.default_error_handler(&error),
+        }
+    }
+
     async fn message_handler(&self, message: &Message) {
-        if let Some(data) = &message.text() {
-            self.text_message_heandler(message, data).await
+        self.membership_handler(message).await;
+
+        if let Some(content) = Self::record_content(message) {
+            self.text_message_heandler(message, content).await
+        } else if message.document().is_some()
+            && message.chat.is_private()
+            && message.forward().is_none()
+        {
+            CommandHandler::import_handler(self, message).await
         } else {
             self.default_message_handler(message).await
         }
     }
 
-    async fn text_message_heandler(&self, message: &Message, data: &str) {
+    /// keep track of group members, to know which libraries they can search.
+    ///
+    /// Members are only known from the messages the bot gets, so the privacy
+    /// mode of the bot has to be disabled for groups.
+    async fn membership_handler(&self, message: &Message) {
+        if message.chat.is_private() {
+            return;
+        }
+        let chat_id = message.chat.id.0;
+
+        if let Some(user) = message.left_chat_member() {
+            let user_id: i64 = user.id.0.try_into().unwrap();
+            self.members.lock().unwrap().remove(&(user_id, chat_id));
+            if let Err(error) = self
+                .controller
+                .remove_membership(&user.id.0.try_into().unwrap(), chat_id)
+                .await
+            {
+                self.controller.err_handler(error);
+            }
+            return;
+        }
+
+        let mut members: Vec<&User> = message.from().into_iter().collect();
+        if let Some(new_members) = message.new_chat_members() {
+            members.extend(new_members.iter());
+        }
+
+        for user in members.into_iter().filter(|user| !user.is_bot) {
+            let user_id: i64 = user.id.0.try_into().unwrap();
+            if self.members.lock().unwrap().contains(&(user_id, chat_id)) {
+                continue;
+            }
+
+            match self
+                .controller
+                .add_membership(&user_id, &display_username(user), chat_id)
+                .await
+            {
+                Ok(_) => {
+                    self.members.lock().unwrap().insert((user_id, chat_id));
+                }
+                Err(error) => self.controller.err_handler(error),
+            }
+        }
+    }
+
+    /// extract the recordable content of a message, `None` if not supported.
+    fn record_content(message: &Message) -> Option<RecordContent> {
+        if let Some(text) = message.text() {
+            return Some(RecordContent {
+                kind: MediaKind::Text,
+                text: text.to_string(),
+                entities: entities_to_json(message.entities()),
+                file_id: None,
+                file_unique_id: String::new(),
+                created_at: None,
+            });
+        }
+
+        // captions are kept as the searchable text of media records
+        let caption = message.caption().unwrap_or_default().to_string();
+
+        let (kind, text, file_id, file_unique_id) = if let Some(sticker) = message.sticker() {
+            (
+                MediaKind::Sticker,
+                sticker.emoji.to_owned().unwrap_or_default(),
+                &sticker.file_id,
+                &sticker.file_unique_id,
+            )
+        } else if let Some(photo) = message.photo().and_then(|sizes| sizes.last()) {
+            // the last one is the largest size
+            (
+                MediaKind::Photo,
+                caption,
+                &photo.file_id,
+                &photo.file_unique_id,
+            )
+        } else if let Some(animation) = message.animation() {
+            (
+                MediaKind::Animation,
+                caption,
+                &animation.file_id,
+                &animation.file_unique_id,
+            )
+        } else if let Some(voice) = message.voice() {
+            (
+                MediaKind::Voice,
+                caption,
+                &voice.file_id,
+                &voice.file_unique_id,
+            )
+        } else if let Some(video) = message.video() {
+            (
+                MediaKind::Video,
+                caption,
+                &video.file_id,
+                &video.file_unique_id,
+            )
+        } else {
+            return None;
+        };
+
+        Some(RecordContent {
+            kind,
+            text,
+            entities: entities_to_json(message.caption_entities()),
+            file_id: Some(file_id.to_owned()),
+            file_unique_id: file_unique_id.to_owned(),
+            created_at: None,
+        })
+    }
+
+    async fn text_message_heandler(&self, message: &Message, content: RecordContent) {
         let forward = match message.forward() {
             Some(forward) => forward,
             None => {
-                if data.starts_with("/") {
+                if content.kind == MediaKind::Text && content.text.starts_with("/") {
                     self.command_hanler(message).await;
-                } else {
+                } else if message.chat.is_private() {
+                    // groups are chatting, not talking to the bot
                     self.send_text_reply(message, BOT_TEXT_FORWARDED_ONLY).await;
                 }
                 return;
@@ -247,66 +984,55 @@ impl BotServer {
 
         match &forward.from {
             ForwardedFrom::User(user) if !user.is_bot => {
-                let username = match &user.username {
-                    Some(username) => format!("@{}", username),
-                    None => user.first_name.to_owned(),
-                };
-
-                if let Err(err) = self
-                    .controller
-                    .add_record(user.id.0.try_into().unwrap(), &username, data.to_string())
-                    .await
-                {
-                    self.controller.err_handler(err);
-                    return;
-                }
-                let mut vars = HashMap::new();
-                vars.insert("data".to_string(), data);
-
-                self.send_text_reply(message, &BOT_TEXT_NOTED.format(&vars).unwrap())
-                    .await;
+                let username = display_username(user);
 
                 let from = match message.from() {
                     Some(from) => from,
                     None => return,
                 };
 
-                if from.id == user.id {
-                    return;
-                }
+                let data = record_label(&content.kind, &content.text);
 
-                if match self
+                let record = match self
                     .controller
-                    .get_user_notify(&user.id.0.try_into().unwrap())
+                    .add_record(
+                        user.id.0.try_into().unwrap(),
+                        Some(&username),
+                        from.id.0.try_into().unwrap(),
+                        Some(&display_username(from)),
+                        chat_library(message),
+                        content,
+                    )
                     .await
                 {
-                    Ok(notify) => notify,
-                    Err(error) => {
-                        log_error_ln!("{}", error);
+                    Ok(AddRecordResult::Added(record)) => record,
+                    Ok(AddRecordResult::OptedOut) => {
+                        self.send_text_reply(message, BOT_TEXT_OPTED_OUT).await;
                         return;
                     }
-                } {
-                    let mut vars = HashMap::new();
-                    let user_id = from.id.to_string();
-                    let data = data.to_string();
-
-                    vars.insert("username".to_string(), &from.first_name);
-                    vars.insert("user_id".to_string(), &user_id);
-                    vars.insert("data".to_string(), &data);
-
-                    match self
-                        .bot
-                        .send_message(user.id, escape(&BOT_TEXT_NOTICE.format(&vars).unwrap()))
-                        .parse_mode(ParseMode::MarkdownV2)
-                        .send()
-                        .await
-                    {
-                        Ok(result) => {
-                            log_debug_ln!("message sent {:?}", result)
-                        }
-                        Err(err) => self.default_error_handler(&err),
+                    Ok(AddRecordResult::Duplicate(record)) => {
+                        let mut vars = HashMap::new();
+                        vars.insert("id".to_string(), record.id.to_string());
+                        self.send_text_reply(message, &BOT_TEXT_DUPLICATED.format(&vars).unwrap())
+                            .await;
+                        return;
                     }
-                }
+                    Err(err) => {
+                        self.controller.err_handler(err);
+                        return;
+                    }
+                };
+                let mut vars = HashMap::new();
+                vars.insert("data".to_string(), &data);
+
+                let noted = match record.approved {
+                    true => BOT_TEXT_NOTED,
+                    false => BOT_TEXT_NOTED_PENDING,
+                };
+                self.send_text_reply(message, &noted.format(&vars).unwrap())
+                    .await;
+
+                self.send_record_notice(user.id, from, &record).await;
             }
             ForwardedFrom::User(_) => {
                 self.send_text_reply(message, BOT_TEXT_NO_BOT).await;
@@ -320,6 +1046,110 @@ impl BotServer {
         }
     }
 
+    /// tell `author` that `from` recorded them, with the review buttons if the
+    /// record waits for their consent.
+    async fn send_record_notice(&self, author: UserId, from: &User, record: &RecordModel) {
+        if from.id == author {
+            return;
+        }
+
+        // pending records always notify, the user has to review them
+        if !record.approved
+            || match self
+                .controller
+                .get_user_notify(&author.0.try_into().unwrap())
+                .await
+            {
+                Ok(notify) => notify,
+                Err(error) => {
+                    log_error_ln!("{}", error);
+                    return;
+                }
+            }
+        {
+            let mut vars = HashMap::new();
+            let user_id = from.id.to_string();
+            let data = record_label(&record.kind, &record.message);
+
+            vars.insert("username".to_string(), &from.first_name);
+            vars.insert("user_id".to_string(), &user_id);
+            vars.insert("data".to_string(), &data);
+
+            let mut notice = BOT_TEXT_NOTICE.format(&vars).unwrap();
+            if !record.approved {
+                notice = format!("{}\n\n{}", notice, BOT_TEXT_PENDING_NOTICE);
+            }
+
+            let mut request = self
+                .bot
+                .send_message(author, escape(&notice))
+                .parse_mode(ParseMode::MarkdownV2);
+            if !record.approved {
+                request = request.reply_markup(CommandHandler::generate_review_keyboard(record.id));
+            }
+
+            match request.send().await {
+                Ok(result) => {
+                    log_debug_ln!("message sent {:?}", result)
+                }
+                Err(err) => self.default_error_handler(&err),
+            }
+        }
+    }
+
+    /// tell authors about records `from` imported, `records` being pairs of
+    /// the Telegram user ID of the author and the record.
+    ///
+    /// Pending records are sent one by one to be reviewed, the others are
+    /// counted in one notice per author.
+    async fn send_import_notices(&self, from: &User, records: &[(i64, RecordModel)]) {
+        let mut approved: Vec<(i64, usize)> = vec![];
+        for (author, record) in records {
+            if record.approved {
+                match approved.iter_mut().find(|(id, _)| *id == *author) {
+                    Some((_, count)) => *count += 1,
+                    None => approved.push((*author, 1)),
+                }
+            } else {
+                self.send_record_notice(UserId(*author as u64), from, record)
+                    .await;
+            }
+        }
+
+        for (author, count) in approved {
+            if UserId(author as u64) == from.id {
+                continue;
+            }
+            match self.controller.get_user_notify(&author).await {
+                Ok(true) => (),
+                Ok(false) => continue,
+                Err(error) => {
+                    log_error_ln!("{}", error);
+                    continue;
+                }
+            }
+
+            let mut vars = HashMap::new();
+            vars.insert("username".to_string(), from.first_name.to_owned());
+            vars.insert("user_id".to_string(), from.id.to_string());
+            vars.insert("count".to_string(), count.to_string());
+
+            match self
+                .bot
+                .send_message(
+                    UserId(author as u64),
+                    escape(&BOT_TEXT_IMPORT_NOTICE.format(&vars).unwrap()),
+                )
+                .parse_mode(ParseMode::MarkdownV2)
+                .send()
+                .await
+            {
+                Ok(result) => log_debug_ln!("message sent {:?}", result),
+                Err(err) => self.default_error_handler(&err),
+            }
+        }
+    }
+
     async fn command_hanler(&self, message: &Message) {
         let msg = match message.text() {
             Some(msg) => msg,
@@ -339,8 +1169,15 @@ impl BotServer {
             None => return,
         };
 
-        let commands = match Commands::parse(msg, bot_username) {
+        let commands = match Commands::parse(msg, &bot_username) {
             Ok(c) => c,
+            Err(ParseError::IncorrectFormat(error))
+                if parse_command(msg, &bot_username).map(|(command, _)| command) == Some("del") =>
+            {
+                log_debug_ln!("{}", error);
+                CommandHandler::del_usage_handler(&self, message).await;
+                return;
+            }
             Err(error) => {
                 log_warn_ln!("{}", error);
                 return;
@@ -352,7 +1189,10 @@ impl BotServer {
             Commands::About => CommandHandler::about_handler(&self, message).await,
             Commands::Mute => CommandHandler::notify_handler(&self, message, true).await,
             Commands::Unmute => CommandHandler::notify_handler(&self, message, false).await,
-            Commands::List { mut username } => {
+            Commands::List {
+                mut username,
+                filter,
+            } => {
                 if username == "me" {
                     if let Some(from) = message.from() {
                         if let Some(_username) = &from.username {
@@ -363,13 +1203,36 @@ impl BotServer {
 
                 if username.starts_with("@") {
                     // always start from page=0
-                    CommandHandler::list_handler(&self, message, &username, 0).await;
+                    CommandHandler::list_handler(&self, message, &username, &filter, 0).await;
                 } else {
                     self.send_text_reply(message, BOT_TEXT_SHOULD_START_WITH_AT)
                         .await;
                 }
             }
-            Commands::Del { id } => CommandHandler::del_handler(&self, message, id).await,
+            Commands::Del { ids } => CommandHandler::del_handler(&self, message, &ids).await,
+            Commands::Delall => CommandHandler::del_all_handler(&self, message).await,
+            Commands::Recorded => CommandHandler::recorded_handler(&self, message).await,
+            Commands::Random { username } => {
+                CommandHandler::random_handler(&self, message, username.trim()).await
+            }
+            Commands::Trending => CommandHandler::trending_handler(&self, message).await,
+            Commands::Stats => CommandHandler::stats_handler(&self, message).await,
+            Commands::Export { format, username } => {
+                CommandHandler::export_handler(&self, message, &format, &username).await
+            }
+            Commands::Import => CommandHandler::import_handler(&self, message).await,
+            Commands::Trash => CommandHandler::trash_handler(&self, message).await,
+            Commands::Restore { id } => CommandHandler::restore_handler(&self, message, id).await,
+            Commands::Scope { scope } => {
+                CommandHandler::scope_handler(&self, message, &scope).await
+            }
+            Commands::Tag { id, tags } => {
+                CommandHandler::tag_handler(&self, message, id, &tags).await
+            }
+            Commands::Optout => CommandHandler::opt_out_handler(&self, message, true).await,
+            Commands::Optin => CommandHandler::opt_out_handler(&self, message, false).await,
+            Commands::Consent => CommandHandler::consent_handler(&self, message, true).await,
+            Commands::Noconsent => CommandHandler::consent_handler(&self, message, false).await,
             Commands::Start => CommandHandler::setup_handler(&self, message).await,
         }
     }
@@ -384,7 +1247,9 @@ impl BotServer {
             message.kind,
             message.from()
         );
-        self.send_text_reply(message, BOT_TEXT_MESSAGE_ONLY).await;
+        if message.chat.is_private() {
+            self.send_text_reply(message, BOT_TEXT_MESSAGE_ONLY).await;
+        }
     }
 
     pub async fn send_text_message(&self, message: &Message, text: &str) -> Option<i32> {
@@ -426,6 +1291,59 @@ impl BotServer {
         }
     }
 
+    pub async fn send_document_reply(
+        &self,
+        message: &Message,
+        file_name: String,
+        file: Vec<u8>,
+    ) -> Option<i32> {
+        match &self
+            .bot
+            .send_document(
+                message.chat.id,
+                InputFile::memory(file).file_name(file_name),
+            )
+            .reply_to_message_id(message.id)
+            .send()
+            .await
+        {
+            Ok(result) => {
+                log_debug_ln!("document sent {:?}", result);
+                Some(result.id)
+            }
+            Err(error) => {
+                self.default_error_handler(error);
+                None
+            }
+        }
+    }
+
+    pub async fn send_text_reply_with_inline_key(
+        &self,
+        message: &Message,
+        text: &str,
+        keyboard: ReplyMarkup,
+    ) -> Option<i32> {
+        match &self
+            .bot
+            .send_message(message.chat.id, text)
+            .reply_to_message_id(message.id)
+            .reply_markup(keyboard)
+            .parse_mode(ParseMode::MarkdownV2)
+            .send()
+            .await
+        {
+            Ok(result) => {
+                log_debug_ln!("reply sent {:?}", result);
+                Some(result.id)
+            }
+            Err(error) => {
+                self.default_error_handler(error);
+                None
+            }
+        }
+    }
+
     pub async fn edit_text_reply_with_inline_key(
         &self,
         message: &Message,